@@ -1,8 +1,235 @@
 import { logger } from '../utils/logger'
 
+export interface SocketDetails {
+  inode?: number | null
+  peer_inode?: number | null
+  uid?: number | null
+  rx_queue?: number | null
+  tx_queue?: number | null
+  retransmits?: number | null
+  timer?: string | { unknown: number } | null
+  timer_expires_ms?: number | null
+  rtt_us?: number | null
+  cwnd?: number | null
+  bytes_sent?: number | null
+  bytes_received?: number | null
+  /** Bytes per second since the previous monitor tick */
+  send_rate?: number | null
+  recv_rate?: number | null
+}
+
+export interface TopTalker {
+  pid: number
+  process_name: string
+  local_address: string
+  local_port: number
+  remote_address: string
+  remote_port: number
+  send_rate: number
+  recv_rate: number
+}
+
+export interface ThroughputStats {
+  at?: string | null
+  send_rate: number
+  recv_rate: number
+  active_connections: number
+  top: TopTalker[]
+}
+
+export interface ProcessContext {
+  net_namespace?: number | null
+  foreign_namespace: boolean
+  container_id?: string | null
+  container_runtime?: string | null
+  cgroup?: string | null
+  systemd_unit?: string | null
+  exe?: string | null
+}
+
+export interface ForwardTarget {
+  forwarder: string
+  host: string
+  port?: number | null
+}
+
+export interface ConnectionLabel {
+  label: string
+  color?: string | null
+}
+
+export type LabelMatch =
+  | { match: 'port', port: number }
+  | { match: 'port_range', start: number, end: number }
+  | { match: 'process', pattern: string }
+  | { match: 'address', address: string }
+
+export type LabelRule = {
+  id: string
+  label: string
+  color?: string | null
+} & LabelMatch
+
+export interface Settings {
+  version: number
+  auto_refresh: boolean
+  refresh_interval_secs: number
+  default_protocol: string
+  backend: string | null
+  hidden_columns: string[]
+  all_namespaces: boolean
+  reverse_dns: boolean
+  geoip_databases: string[]
+  monitor_interval_secs: number
+  history_enabled: boolean
+  history_retention_days: number
+  leak_detection: LeakThresholds
+}
+
+export type RuleCondition =
+  | { rule: 'must_listen', port: number, protocol?: 'tcp' | 'udp' | 'sctp' | null, address?: string | null }
+  | { rule: 'no_wildcard_listener', allow_ports: number[], allow_processes: string[] }
+  | { rule: 'must_not_connect', process: string, remote: string, port?: number | null }
+
+export type AlertRule = {
+  id: string
+  name: string
+  enabled: boolean
+} & RuleCondition
+
+export interface AlertEvent {
+  rule_id: string
+  rule_name: string
+  at: string
+  message: string
+  resolved: boolean
+}
+
+export interface BaselineListener {
+  protocol: string
+  address: string
+  port: number
+  process_name: string
+  exe?: string | null
+}
+
+export interface Baseline {
+  id: string
+  name: string
+  created_at: string
+  protocol: string
+  /** Filters the baseline was captured with; drift is checked with the same ones */
+  criteria: Record<string, unknown>
+  listeners: BaselineListener[]
+}
+
+export interface DriftEntry {
+  kind: 'new' | 'missing' | 'process_changed' | 'widened'
+  protocol: string
+  port: number
+  expected: BaselineListener | null
+  actual: BaselineListener | null
+  message: string
+}
+
+export interface DriftReport {
+  baseline_id: string
+  baseline_name: string
+  generated_at: string
+  unchanged: number
+  entries: DriftEntry[]
+}
+
+export interface GroupProcess {
+  pid: number
+  process_name: string
+  connections: number
+}
+
+export interface RemoteGroup {
+  key: string
+  hostname?: string | null
+  peer?: PeerInfo | null
+  hosts: number
+  connections: number
+  states: Record<string, number>
+  remote_ports: number[]
+  processes: GroupProcess[]
+}
+
+export interface LeakThresholds {
+  growth_samples: number
+  min_growth: number
+  close_wait_threshold: number
+}
+
+export interface LeakAlert {
+  pid: number
+  process_name: string
+  state: string
+  kind: 'growth' | 'close_wait'
+  count: number
+  growth: number
+  since: string
+  samples: number[]
+  message: string
+}
+
+export interface ConnectionRecord {
+  protocol: string
+  local_address: string
+  local_port: number
+  remote_address: string
+  remote_port: number
+  state: string
+  pid: number
+  process_name: string
+}
+
+export interface HistoryEvent {
+  at: string
+  kind: 'opened' | 'closed' | 'state_changed'
+  connection: ConnectionRecord
+  previous_state?: string
+}
+
+export interface PeerInfo {
+  scope: 'loopback' | 'private' | 'shared' | 'link_local' | 'multicast' | 'public'
+  asn?: number | null
+  org?: string | null
+  country?: string | null
+}
+
+export interface GeoIpDatabaseInfo {
+  path: string
+  format: 'mmdb' | 'csv'
+  description: string
+}
+
+export type InterfaceKind = 'loopback' | 'ethernet' | 'wifi' | 'tunnel' | 'docker_bridge' | 'bridge' | 'veth' | 'other'
+
+export interface InterfaceInfo {
+  name: string
+  kind: InterfaceKind
+  flags: string[]
+}
+
+export interface NetworkInterface extends InterfaceInfo {
+  addresses: string[]
+  /** Netmask of each address as a prefix length, in the same order */
+  prefix_lengths: number[]
+}
+
+export interface Finding {
+  kind: 'deleted_executable' | 'temp_executable' | 'interpreter_listener' | 'suspicious_port'
+  severity: 'low' | 'medium' | 'high'
+  message: string
+}
+
 export interface ConnectionInfo {
   id: string
   protocol: string
+  socket_type?: string | null
   local_address: string
   local_port: number
   remote_address: string
@@ -10,6 +237,15 @@ export interface ConnectionInfo {
   state: string
   pid: number
   process_name: string
+  socket?: SocketDetails
+  context?: ProcessContext
+  forward?: ForwardTarget | null
+  service_name?: string | null
+  remote_hostname?: string | null
+  remote_peer?: PeerInfo | null
+  labels?: ConnectionLabel[]
+  interface?: InterfaceInfo | null
+  findings?: Finding[]
 }
 
 // Check if we're running in Tauri environment
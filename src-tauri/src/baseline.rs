@@ -0,0 +1,373 @@
+use crate::filter::{filter_connections_with, validate_protocol_filter, FilterCriteria};
+use crate::models::{ConnectionInfo, Protocol};
+use crate::ports::parse_bind_address;
+use crate::fs_util::{JsonStore, StoreError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// File name of the baseline store inside the app data dir
+pub const BASELINES_FILE: &str = "baselines.json";
+
+/// Bumped whenever the on-disk layout changes incompatibly
+const BASELINES_FILE_VERSION: u32 = 1;
+
+/// One approved listening socket
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineListener {
+    pub protocol: String,
+    /// Canonical bind address (`0.0.0.0`, `::`, `127.0.0.1`, ...)
+    pub address: String,
+    pub port: u16,
+    pub process_name: String,
+    #[serde(default)]
+    pub exe: Option<String>,
+}
+
+impl BaselineListener {
+    fn from_connection(conn: &ConnectionInfo) -> Self {
+        Self {
+            protocol: conn.protocol.to_string(),
+            address: parse_bind_address(&conn.local_address)
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|| conn.local_address.clone()),
+            port: conn.local_port,
+            process_name: conn.process_name.clone(),
+            exe: conn.context.exe.clone(),
+        }
+    }
+
+    fn ip(&self) -> Option<IpAddr> {
+        parse_bind_address(&self.address)
+    }
+
+    /// Same program, judged by executable when both sides know it
+    fn same_process(&self, other: &BaselineListener) -> bool {
+        match (&self.exe, &other.exe) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.process_name == other.process_name,
+        }
+    }
+}
+
+/// A named snapshot of the listeners approved in a hardening review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Protocol filter the baseline was captured with; drift uses the same one
+    pub protocol: String,
+    /// Other filters the baseline was captured with; drift uses the same ones
+    #[serde(default)]
+    pub criteria: FilterCriteria,
+    pub listeners: Vec<BaselineListener>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// Listening now, not in the baseline
+    New,
+    /// In the baseline, not listening now
+    Missing,
+    /// Same protocol, address and port, different program
+    ProcessChanged,
+    /// Was bound to loopback only, now to the wildcard address
+    Widened,
+}
+
+/// One difference between a baseline and the current listeners
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftEntry {
+    pub kind: DriftKind,
+    pub protocol: String,
+    pub port: u16,
+    pub expected: Option<BaselineListener>,
+    pub actual: Option<BaselineListener>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub baseline_id: String,
+    pub baseline_name: String,
+    pub generated_at: DateTime<Utc>,
+    /// Baseline listeners still present and unchanged
+    pub unchanged: usize,
+    pub entries: Vec<DriftEntry>,
+}
+
+/// Errors from capturing, comparing or persisting baselines
+#[derive(Debug, Clone)]
+pub enum BaselineError {
+    Invalid(String),
+    /// No baseline with this id
+    NotFound(String),
+    /// Reading or writing the baseline file failed
+    Io(String),
+}
+
+impl fmt::Display for BaselineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaselineError::Invalid(msg) => write!(f, "Invalid baseline: {}", msg),
+            BaselineError::NotFound(id) => write!(f, "No baseline with id {}", id),
+            BaselineError::Io(msg) => write!(f, "Baseline store I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BaselineError {}
+
+impl From<StoreError> for BaselineError {
+    fn from(e: StoreError) -> Self {
+        BaselineError::Io(e.0)
+    }
+}
+
+/// Listening sockets of the host namespace that pass the filters, deduplicated
+///
+/// Several pids sharing one socket (pre-fork servers, SO_REUSEPORT) count once.
+fn listeners(connections: &[ConnectionInfo], protocol: &str, criteria: &FilterCriteria) -> Vec<BaselineListener> {
+    let mut listeners: Vec<BaselineListener> = filter_connections_with(connections, protocol, None, criteria)
+        .iter()
+        .filter(|conn| conn.state.is_listening() && !conn.context.foreign_namespace)
+        .filter(|conn| !matches!(conn.protocol, Protocol::Unix(_)))
+        .map(BaselineListener::from_connection)
+        .collect();
+    listeners.sort();
+    listeners.dedup();
+    listeners
+}
+
+/// Compare the current listeners against `baseline`
+///
+/// Listeners are paired per protocol and port: an exact address match is
+/// either unchanged or a process change; a wildcard listener where the
+/// baseline had loopback is a widening; anything left over is new or missing.
+pub fn drift_report(baseline: &Baseline, connections: &[ConnectionInfo]) -> DriftReport {
+    let current = listeners(connections, &baseline.protocol, &baseline.criteria);
+
+    let mut ports: BTreeMap<(String, u16), (Vec<BaselineListener>, Vec<BaselineListener>)> = BTreeMap::new();
+    for listener in &baseline.listeners {
+        ports.entry((listener.protocol.clone(), listener.port)).or_default().0.push(listener.clone());
+    }
+    for listener in current {
+        ports.entry((listener.protocol.clone(), listener.port)).or_default().1.push(listener);
+    }
+
+    let mut entries = Vec::new();
+    let mut unchanged = 0;
+    for ((protocol, port), (mut expected, actual)) in ports {
+        let mut unmatched = Vec::new();
+        for now in actual {
+            if let Some(index) = expected.iter().position(|before| before.address == now.address) {
+                let before = expected.remove(index);
+                if before.same_process(&now) {
+                    unchanged += 1;
+                } else {
+                    entries.push(DriftEntry {
+                        kind: DriftKind::ProcessChanged,
+                        protocol: protocol.clone(),
+                        port,
+                        message: format!(
+                            "{} {}:{} is now served by {} instead of {}",
+                            protocol, now.address, port, now.process_name, before.process_name
+                        ),
+                        expected: Some(before),
+                        actual: Some(now),
+                    });
+                }
+            } else {
+                unmatched.push(now);
+            }
+        }
+
+        for now in unmatched {
+            let widened_from = now
+                .ip()
+                .filter(|ip| ip.is_unspecified())
+                .and_then(|_| expected.iter().position(|before| before.ip().is_some_and(|ip| ip.is_loopback())));
+            match widened_from {
+                Some(index) => {
+                    let before = expected.remove(index);
+                    entries.push(DriftEntry {
+                        kind: DriftKind::Widened,
+                        protocol: protocol.clone(),
+                        port,
+                        message: format!(
+                            "{} port {} ({}) widened from {} to {}",
+                            protocol, port, now.process_name, before.address, now.address
+                        ),
+                        expected: Some(before),
+                        actual: Some(now),
+                    });
+                }
+                None => entries.push(DriftEntry {
+                    kind: DriftKind::New,
+                    protocol: protocol.clone(),
+                    port,
+                    message: format!("{} ({}) listens on {} {}:{}", now.process_name, now.exe.as_deref().unwrap_or("?"), protocol, now.address, port),
+                    expected: None,
+                    actual: Some(now),
+                }),
+            }
+        }
+
+        for before in expected {
+            entries.push(DriftEntry {
+                kind: DriftKind::Missing,
+                protocol: protocol.clone(),
+                port,
+                message: format!("{} no longer listens on {} {}:{}", before.process_name, protocol, before.address, port),
+                expected: Some(before),
+                actual: None,
+            });
+        }
+    }
+
+    DriftReport {
+        baseline_id: baseline.id.clone(),
+        baseline_name: baseline.name.clone(),
+        generated_at: Utc::now(),
+        unchanged,
+        entries,
+    }
+}
+
+/// Saved baselines, persisted like label and alert rules
+pub struct BaselineStore {
+    baselines: JsonStore<Baseline>,
+}
+
+impl BaselineStore {
+    pub fn new() -> Self {
+        Self {
+            baselines: JsonStore::new("baselines", BASELINES_FILE_VERSION),
+        }
+    }
+
+    /// Read baselines from `path` and persist to it from now on; a missing file means none
+    pub fn load(&self, path: PathBuf) -> Result<usize, BaselineError> {
+        Ok(self.baselines.load(path, |_| true)?)
+    }
+
+    /// Saved baselines, newest first
+    pub fn baselines(&self) -> Vec<Baseline> {
+        let mut baselines = self.baselines.items();
+        baselines.sort_by_key(|baseline| std::cmp::Reverse(baseline.created_at));
+        baselines
+    }
+
+    pub fn get(&self, id: &str) -> Result<Baseline, BaselineError> {
+        self.baselines
+            .items()
+            .into_iter()
+            .find(|baseline| baseline.id == id)
+            .ok_or_else(|| BaselineError::NotFound(id.to_string()))
+    }
+
+    /// Save the current listeners matching `protocol` and `criteria` as a new baseline
+    pub fn capture(
+        &self,
+        name: &str,
+        connections: &[ConnectionInfo],
+        protocol: &str,
+        criteria: &FilterCriteria,
+    ) -> Result<Baseline, BaselineError> {
+        if name.trim().is_empty() {
+            return Err(BaselineError::Invalid("name must not be empty".to_string()));
+        }
+        validate_protocol_filter(protocol).map_err(|e| BaselineError::Invalid(e.to_string()))?;
+        let baseline = Baseline {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            created_at: Utc::now(),
+            protocol: protocol.to_string(),
+            criteria: criteria.clone(),
+            listeners: listeners(connections, protocol, criteria),
+        };
+
+        self.baselines.update(|baselines| {
+            baselines.push(baseline.clone());
+            Ok(baseline)
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), BaselineError> {
+        self.baselines.update(|baselines| {
+            let before = baselines.len();
+            baselines.retain(|baseline| baseline.id != id);
+            if baselines.len() == before {
+                return Err(BaselineError::NotFound(id.to_string()));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    fn listener(address: &str, port: u16, process: &str) -> ConnectionInfo {
+        let mut conn = ConnectionInfo::new(Protocol::TCP, address.to_string(), port, "0.0.0.0".to_string(), 0, ConnectionState::Listening, 1, process.to_string());
+        conn.context.exe = Some(format!("/usr/bin/{}", process));
+        conn
+    }
+
+    #[test]
+    fn test_drift_report() {
+        let store = BaselineStore::new();
+        let approved = vec![
+            listener("0.0.0.0", 22, "sshd"),
+            listener("127.0.0.1", 5432, "postgres"),
+            listener("127.0.0.1", 6379, "redis-server"),
+            listener("0.0.0.0", 8080, "nginx"),
+            // Two workers of one server share the socket
+            listener("::", 443, "nginx"),
+            listener("::", 443, "nginx"),
+        ];
+        let baseline = store.capture("approved", &approved, "all", &FilterCriteria::default()).unwrap();
+        assert_eq!(baseline.listeners.len(), 5);
+
+        let now = vec![
+            listener("0.0.0.0", 22, "sshd"),
+            listener("0.0.0.0", 5432, "postgres"),
+            listener("0.0.0.0", 8080, "python3"),
+            listener("::", 443, "nginx"),
+            listener("0.0.0.0", 4444, "nc"),
+        ];
+        let report = drift_report(&baseline, &now);
+        assert_eq!(report.unchanged, 2);
+        let kinds: Vec<(DriftKind, u16)> = report.entries.iter().map(|e| (e.kind, e.port)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (DriftKind::New, 4444),
+                (DriftKind::Widened, 5432),
+                (DriftKind::Missing, 6379),
+                (DriftKind::ProcessChanged, 8080),
+            ]
+        );
+        assert_eq!(report.entries[1].message, "TCP port 5432 (postgres) widened from 127.0.0.1 to 0.0.0.0");
+    }
+
+    #[test]
+    fn test_drift_reuses_capture_filters() {
+        let store = BaselineStore::new();
+        let criteria = FilterCriteria { process: Some("nginx".to_string()), ..FilterCriteria::default() };
+        let baseline = store.capture("web", &[listener("0.0.0.0", 443, "nginx")], "tcp", &criteria).unwrap();
+
+        // Listeners outside the captured filter are not drift
+        let now = vec![listener("0.0.0.0", 443, "nginx"), listener("0.0.0.0", 22, "sshd")];
+        let report = drift_report(&baseline, &now);
+        assert_eq!(report.unchanged, 1);
+        assert!(report.entries.is_empty());
+    }
+}
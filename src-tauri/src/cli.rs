@@ -0,0 +1,156 @@
+use crate::collector::ConnectionCollector;
+use crate::wait::{wait_for, WaitCondition, WaitCriteria, WaitError, DEFAULT_POLL_INTERVAL, DEFAULT_WAIT_TIMEOUT};
+use std::time::Duration;
+
+const USAGE: &str = "\
+usage: portviewer wait-for-port <port> [options]
+       portviewer wait-for-release <port> [options]
+
+options:
+  --protocol <tcp|udp|sctp>  socket protocol (default tcp)
+  --address <ip>             bind address to match; a wildcard listener matches any
+  --process <name>           only sockets whose process name contains <name>
+  --timeout <seconds>        give up after this long (default 30)
+  --interval <ms>            poll interval (default 500)
+
+exit status: 0 when the condition holds, 1 on timeout, 2 on bad arguments";
+
+/// Run a headless subcommand if the arguments name one, returning its exit code
+///
+/// Anything else (including no arguments) returns `None` and the GUI starts.
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let condition = match args.first().map(String::as_str) {
+        Some("wait-for-port") => WaitCondition::Listening,
+        Some("wait-for-release") => WaitCondition::Released,
+        _ => return None,
+    };
+    attach_parent_console();
+
+    let options = match parse_wait_args(&args[1..]) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return Some(2);
+        }
+    };
+    Some(run_wait(condition, options))
+}
+
+/// Release builds on Windows use the GUI subsystem and start without a
+/// console, so borrow the one of the shell that started us. cmd.exe does not
+/// wait for GUI programs; scripts should use `start /wait` for the exit code.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // SAFETY: no preconditions; failing only means there is no parent console
+    let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+struct WaitArgs {
+    criteria: WaitCriteria,
+    timeout: Duration,
+    interval: Duration,
+}
+
+fn parse_wait_args(args: &[String]) -> Result<WaitArgs, String> {
+    let mut parsed = WaitArgs {
+        criteria: WaitCriteria::default(),
+        timeout: DEFAULT_WAIT_TIMEOUT,
+        interval: DEFAULT_POLL_INTERVAL,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            if parsed.criteria.port != 0 {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            parsed.criteria.port = arg.parse().map_err(|_| format!("invalid port '{}'", arg))?;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--protocol" => parsed.criteria.protocol = Some(value.clone()),
+            "--address" => parsed.criteria.address = Some(value.clone()),
+            "--process" => parsed.criteria.process = Some(value.clone()),
+            "--timeout" => {
+                let seconds: f64 = value.parse().map_err(|_| format!("invalid timeout '{}'", value))?;
+                parsed.timeout = Duration::try_from_secs_f64(seconds).map_err(|_| format!("invalid timeout '{}'", value))?;
+            }
+            "--interval" => {
+                parsed.interval = Duration::from_millis(value.parse().map_err(|_| format!("invalid interval '{}'", value))?);
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    if parsed.criteria.port == 0 {
+        return Err("missing port".to_string());
+    }
+    Ok(parsed)
+}
+
+fn run_wait(condition: WaitCondition, args: WaitArgs) -> i32 {
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("failed to start runtime: {}", e);
+            return 2;
+        }
+    };
+    let collector = ConnectionCollector::new();
+    let result = runtime.block_on(wait_for(
+        || collector.snapshot(),
+        condition,
+        &args.criteria,
+        args.timeout,
+        args.interval,
+    ));
+
+    match result {
+        Ok(outcome) => {
+            match condition {
+                WaitCondition::Listening => {
+                    for socket in &outcome.sockets {
+                        println!(
+                            "port {} is listening on {} ({} pid {}) after {}ms",
+                            outcome.port, socket.local_address, socket.process_name, socket.pid, outcome.elapsed_ms
+                        );
+                    }
+                }
+                WaitCondition::Released => println!("port {} released after {}ms", outcome.port, outcome.elapsed_ms),
+            }
+            0
+        }
+        Err(e @ WaitError::TimedOut { .. }) => {
+            eprintln!("{}", e);
+            1
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<WaitArgs, String> {
+        parse_wait_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_wait_args() {
+        let parsed = parse(&["5432", "--protocol", "tcp", "--timeout", "2.5"]).unwrap();
+        assert_eq!(parsed.criteria.port, 5432);
+        assert_eq!(parsed.timeout, Duration::from_millis(2500));
+
+        assert_eq!(parse(&["5432", "8080"]).err().unwrap(), "unexpected argument '8080'");
+        assert!(parse(&["--timeout"]).is_err());
+        assert!(parse(&["--protocol", "udp"]).is_err());
+    }
+}
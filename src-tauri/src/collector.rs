@@ -0,0 +1,174 @@
+use crate::enrich::EnrichmentPipeline;
+use crate::models::ConnectionInfo;
+use crate::platform::unified::UnifiedNetworkProvider;
+use crate::platform::{NetworkError, NetworkProvider};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Upper bound for a single collection run before it is reported as hung
+pub const DEFAULT_COLLECTION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Asynchronous front-end for the blocking network providers
+///
+/// Providers shell out to lsof or walk system tables, which can take seconds.
+/// The collector runs them on the blocking thread pool so Tauri commands never
+/// stall the UI thread. Every request gets a generation number; when a newer
+/// request from the same caller starts, older ones still in flight resolve
+/// with `NetworkError::Cancelled`. Callers are keyed (usually by command name)
+/// so the table refresh and a filter query never cancel each other.
+/// The collector owns one long-lived provider so backend diagnostics persist.
+/// Rows from the provider pass through the enrichment pipeline before returning.
+pub struct ConnectionCollector {
+    provider: Arc<UnifiedNetworkProvider>,
+    enrichment: Arc<EnrichmentPipeline>,
+    generation: AtomicU64,
+    /// Latest generation per caller key
+    latest: Mutex<HashMap<&'static str, watch::Sender<u64>>>,
+    timeout: Duration,
+}
+
+impl ConnectionCollector {
+    pub fn new() -> Self {
+        Self::with_timeout(DEFAULT_COLLECTION_TIMEOUT)
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            provider: Arc::new(UnifiedNetworkProvider::new()),
+            enrichment: Arc::new(EnrichmentPipeline::new()),
+            generation: AtomicU64::new(0),
+            latest: Mutex::new(HashMap::new()),
+            timeout,
+        }
+    }
+
+    /// The provider used for every collection run
+    pub fn provider(&self) -> &UnifiedNetworkProvider {
+        &self.provider
+    }
+
+    /// The enrichment steps applied to every collection run
+    pub fn enrichment(&self) -> &EnrichmentPipeline {
+        &self.enrichment
+    }
+
+    /// Collect all connections using the platform provider, then enrich them
+    ///
+    /// Supersedes the previous in-flight request with the same `caller` key.
+    pub async fn collect(&self, caller: &'static str) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        self.run(caller, self.collection_job()).await
+    }
+
+    /// Collect outside the supersession chain
+    ///
+    /// For lookups and watchers that run alongside the table refresh: they
+    /// neither cancel the refresh nor get cancelled by it, but still time out.
+    pub async fn snapshot(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let task = tokio::task::spawn_blocking(self.collection_job());
+        self.with_timeout_of(task).await
+    }
+
+    fn collection_job(&self) -> impl FnOnce() -> Result<Vec<ConnectionInfo>, NetworkError> + Send + 'static {
+        let provider = self.provider.clone();
+        let enrichment = self.enrichment.clone();
+        move || {
+            let mut connections = provider.get_all_connections()?;
+            enrichment.run(&mut connections);
+            Ok(connections)
+        }
+    }
+
+    /// Run a blocking collection job, honouring the timeout and supersession
+    async fn run<F>(&self, caller: &'static str, job: F) -> Result<Vec<ConnectionInfo>, NetworkError>
+    where
+        F: FnOnce() -> Result<Vec<ConnectionInfo>, NetworkError> + Send + 'static,
+    {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut latest = {
+            let mut channels = self.latest.lock().unwrap();
+            let sender = channels.entry(caller).or_insert_with(|| watch::channel(0).0);
+            sender.send_replace(generation);
+            sender.subscribe()
+        };
+        latest.mark_unchanged();
+
+        let task = tokio::task::spawn_blocking(job);
+
+        // Generations only grow, so any change means a newer request exists
+        let superseded = async move {
+            if latest.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        };
+
+        tokio::select! {
+            result = self.with_timeout_of(task) => result,
+            _ = superseded => Err(NetworkError::Cancelled(format!(
+                "request #{} superseded by a newer request", generation
+            ))),
+        }
+    }
+
+    async fn with_timeout_of(
+        &self,
+        task: tokio::task::JoinHandle<Result<Vec<ConnectionInfo>, NetworkError>>,
+    ) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        match tokio::time::timeout(self.timeout, task).await {
+            Ok(Ok(connections)) => connections,
+            Ok(Err(e)) => Err(NetworkError::SystemCallFailed(format!(
+                "Collection task failed: {}", e
+            ))),
+            Err(_) => Err(NetworkError::Timeout(format!(
+                "connection collection did not finish within {}s",
+                self.timeout.as_secs()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+    use std::sync::mpsc;
+
+    /// Stands in for a provider: returns one row tagged with `port` once `gate`
+    /// is released or dropped, so the tests never race the clock
+    fn stub_job(port: u16, gate: Option<mpsc::Receiver<()>>) -> impl FnOnce() -> Result<Vec<ConnectionInfo>, NetworkError> + Send + 'static {
+        move || {
+            if let Some(gate) = gate {
+                let _ = gate.recv();
+            }
+            Ok(vec![ConnectionInfo::new(Protocol::TCP, "127.0.0.1".to_string(), port, "0.0.0.0".to_string(), 0, ConnectionState::Listen, 1, "stub".to_string())])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_supersession_is_per_caller() {
+        let collector = ConnectionCollector::with_timeout(Duration::from_secs(60));
+        let (release, gate) = mpsc::channel();
+        // join! polls in order, so the blocked request registers before the newer one
+        let (slow, other, newer) = tokio::join!(
+            collector.run("table", stub_job(1, Some(gate))),
+            collector.run("filter", stub_job(2, None)),
+            collector.run("table", stub_job(3, None)),
+        );
+        drop(release);
+
+        assert!(matches!(slow, Err(NetworkError::Cancelled(_))));
+        assert_eq!(other.unwrap()[0].local_port, 2);
+        assert_eq!(newer.unwrap()[0].local_port, 3);
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let collector = ConnectionCollector::with_timeout(Duration::from_millis(50));
+        let (release, gate) = mpsc::channel();
+        let result = collector.run("table", stub_job(1, Some(gate))).await;
+        drop(release);
+        assert!(matches!(result, Err(NetworkError::Timeout(_))));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, ProcessContext};
+use crate::platform::procfs::net_namespace_of;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Cgroup path segment prefixes used by container runtimes for their scopes
+const RUNTIME_PREFIXES: [(&str, &str); 4] = [
+    ("docker-", "docker"),
+    ("libpod-", "podman"),
+    ("cri-containerd-", "containerd"),
+    ("crio-", "crio"),
+];
+
+/// Labels each connection with the owning process's executable, network
+/// namespace, container and cgroup, read from `/proc/<pid>/exe`,
+/// `/proc/<pid>/ns/net` and `/proc/<pid>/cgroup`
+pub struct ContainerEnricher;
+
+impl ContainerEnricher {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn process_context(&self, pid: u32) -> ProcessContext {
+        let proc_dir = Path::new("/proc").join(pid.to_string());
+        let cgroup = fs::read_to_string(proc_dir.join("cgroup"))
+            .ok()
+            .and_then(|content| parse_cgroup_file(&content));
+        let (container_runtime, container_id) = cgroup
+            .as_deref()
+            .and_then(container_from_cgroup)
+            .map(|(runtime, id)| (Some(runtime), Some(id)))
+            .unwrap_or((None, None));
+
+        ProcessContext {
+            net_namespace: net_namespace_of(&proc_dir),
+            foreign_namespace: false,
+            container_id,
+            container_runtime,
+            cgroup,
+            systemd_unit: None,
+            // Needs the same privileges as the sockets of other users' processes
+            exe: fs::read_link(proc_dir.join("exe")).ok().map(|path| path.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+impl Enricher for ContainerEnricher {
+    fn name(&self) -> &'static str {
+        "container"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let host_namespace = net_namespace_of(Path::new("/proc/self"));
+        let mut contexts: HashMap<u32, ProcessContext> = HashMap::new();
+
+        for conn in connections.iter_mut() {
+            // Backends that scan other namespaces already know where the socket lives
+            let socket_namespace = conn.context.net_namespace;
+            if conn.pid != 0 {
+                conn.context = contexts
+                    .entry(conn.pid)
+                    .or_insert_with(|| self.process_context(conn.pid))
+                    .clone();
+            }
+            conn.context.net_namespace = socket_namespace.or(conn.context.net_namespace);
+            conn.context.foreign_namespace = matches!(
+                (conn.context.net_namespace, host_namespace),
+                (Some(ns), Some(host)) if ns != host
+            );
+        }
+    }
+}
+
+/// Pick the cgroup path from `/proc/<pid>/cgroup`
+///
+/// Prefers the cgroup v2 entry (`0::/path`), then the systemd v1 hierarchy,
+/// then whatever comes first.
+pub fn parse_cgroup_file(content: &str) -> Option<String> {
+    let entries: Vec<(&str, &str)> = content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let _hierarchy = parts.next()?;
+            Some((parts.next()?, parts.next()?))
+        })
+        .collect();
+
+    ["", "name=systemd"]
+        .iter()
+        .find_map(|wanted| entries.iter().find(|(controllers, _)| controllers == wanted))
+        .or(entries.first())
+        .map(|(_, path)| path.to_string())
+}
+
+/// Extract `(runtime, container id)` from a cgroup path
+///
+/// Handles systemd-style scopes (`docker-<id>.scope`, `libpod-<id>.scope`,
+/// `cri-containerd-<id>.scope`, `crio-<id>.scope`), cgroupfs-style
+/// `/docker/<id>` and `/kubepods/.../<id>`, and LXC's `lxc.payload.<name>`.
+pub fn container_from_cgroup(path: &str) -> Option<(String, String)> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    for (index, segment) in segments.iter().enumerate().rev() {
+        let name = segment.trim_end_matches(".scope");
+
+        if let Some(container) = name.strip_prefix("lxc.payload.") {
+            return Some(("lxc".to_string(), container.to_string()));
+        }
+
+        for (prefix, runtime) in RUNTIME_PREFIXES {
+            if let Some(id) = name.strip_prefix(prefix).filter(|id| is_container_id(id)) {
+                let runtime = if path.contains("kubepods") { "kubernetes" } else { runtime };
+                return Some((runtime.to_string(), id.to_string()));
+            }
+        }
+
+        if is_container_id(name) && index > 0 {
+            let runtime = match segments[..index].iter().find(|s| ["docker", "kubepods", "libpod_parent"].contains(s)) {
+                Some(&"docker") => "docker",
+                Some(&"libpod_parent") => "podman",
+                Some(_) => "kubernetes",
+                None if path.contains("kubepods") => "kubernetes",
+                None => continue,
+            };
+            return Some((runtime.to_string(), name.to_string()));
+        }
+    }
+
+    None
+}
+
+fn is_container_id(candidate: &str) -> bool {
+    candidate.len() == 64 && candidate.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4f2a9c1e0b7d4e6f8a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f6071";
+
+    #[test]
+    fn test_parse_cgroup_file_prefers_unified() {
+        let v1 = "12:memory:/docker/abc\n1:name=systemd:/system.slice/sshd.service\n";
+        assert_eq!(parse_cgroup_file(v1).as_deref(), Some("/system.slice/sshd.service"));
+
+        let v2 = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(parse_cgroup_file(v2).as_deref(), Some("/user.slice/user-1000.slice/session-2.scope"));
+    }
+
+    #[test]
+    fn test_container_from_cgroup() {
+        let docker_scope = format!("/system.slice/docker-{}.scope", ID);
+        assert_eq!(container_from_cgroup(&docker_scope), Some(("docker".to_string(), ID.to_string())));
+
+        let podman = format!("/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{}.scope/container", ID);
+        assert_eq!(container_from_cgroup(&podman), Some(("podman".to_string(), ID.to_string())));
+
+        let kube = format!("/kubepods/burstable/pod1234/{}", ID);
+        assert_eq!(container_from_cgroup(&kube), Some(("kubernetes".to_string(), ID.to_string())));
+
+        assert_eq!(
+            container_from_cgroup("/lxc.payload.web01/init.scope"),
+            Some(("lxc".to_string(), "web01".to_string()))
+        );
+        assert_eq!(container_from_cgroup("/system.slice/nginx.service"), None);
+    }
+}
@@ -0,0 +1,341 @@
+use crate::enrich::Enricher;
+use crate::models::ConnectionInfo;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Semaphore;
+
+/// How long a resolved name is trusted
+pub const DEFAULT_TTL: Duration = Duration::from_secs(10 * 60);
+/// How long a failed lookup suppresses new attempts for the same address
+pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(2 * 60);
+/// Lookups in flight at once; the rest queue behind the semaphore
+pub const DEFAULT_MAX_CONCURRENT: usize = 8;
+/// A lookup still unanswered after this long is cached as a failure
+pub const DEFAULT_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Cached names kept at most; expired ones are dropped first, then the oldest
+pub const DEFAULT_MAX_CACHE_ENTRIES: usize = 4096;
+
+#[derive(Debug, Clone)]
+struct CachedName {
+    hostname: Option<String>,
+    expires: Instant,
+}
+
+/// Parsed hosts file plus the mtime it was read at
+#[derive(Default)]
+struct HostsFile {
+    modified: Option<SystemTime>,
+    names: HashMap<IpAddr, String>,
+}
+
+/// Reverse-DNS cache shared by the enricher and its background lookups
+///
+/// `enrich` never waits on the network: it fills `remote_hostname` from the
+/// hosts file or the cache and queues a lookup for anything unknown, so names
+/// show up on a later snapshot. Failures are cached for a shorter TTL to
+/// avoid hammering the resolver with addresses that have no PTR record.
+pub struct ReverseDnsResolver {
+    enabled: AtomicBool,
+    hosts_path: PathBuf,
+    ttl: Duration,
+    negative_ttl: Duration,
+    lookup_timeout: Duration,
+    max_cache_entries: usize,
+    cache: Mutex<HashMap<IpAddr, CachedName>>,
+    pending: Mutex<HashSet<IpAddr>>,
+    hosts: Mutex<HostsFile>,
+    limit: Arc<Semaphore>,
+}
+
+impl ReverseDnsResolver {
+    pub fn new() -> Self {
+        Self::with_hosts_path(hosts_file_path())
+    }
+
+    pub fn with_hosts_path(hosts_path: impl Into<PathBuf>) -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            hosts_path: hosts_path.into(),
+            ttl: DEFAULT_TTL,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+            lookup_timeout: DEFAULT_LOOKUP_TIMEOUT,
+            max_cache_entries: DEFAULT_MAX_CACHE_ENTRIES,
+            cache: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashSet::new()),
+            hosts: Mutex::new(HostsFile::default()),
+            limit: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT)),
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Hostname known right now, queuing a background lookup when there is none
+    ///
+    /// Uses the hosts file as of the last `refresh_hosts`.
+    pub fn resolve_cached(self: &Arc<Self>, ip: IpAddr) -> Option<String> {
+        if let Some(name) = self.hosts.lock().unwrap().names.get(&ip) {
+            return Some(name.clone());
+        }
+
+        if let Some(entry) = self.cache.lock().unwrap().get(&ip) {
+            if entry.expires > Instant::now() {
+                return entry.hostname.clone();
+            }
+        }
+
+        self.spawn_lookup(ip);
+        None
+    }
+
+    /// Re-read the hosts file if it changed; a missing file simply has no names
+    pub fn refresh_hosts(&self) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let modified = fs::metadata(&self.hosts_path).and_then(|m| m.modified()).ok();
+        if modified != hosts.modified {
+            let content = fs::read_to_string(&self.hosts_path).unwrap_or_default();
+            *hosts = HostsFile {
+                modified,
+                names: parse_hosts(&content),
+            };
+        }
+    }
+
+    fn cache_insert(&self, ip: IpAddr, entry: CachedName) {
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= self.max_cache_entries && !cache.contains_key(&ip) {
+            let now = Instant::now();
+            cache.retain(|_, cached| cached.expires > now);
+            if cache.len() >= self.max_cache_entries {
+                let oldest = cache.iter().min_by_key(|(_, cached)| cached.expires).map(|(ip, _)| *ip);
+                if let Some(oldest) = oldest {
+                    cache.remove(&oldest);
+                }
+            }
+        }
+        cache.insert(ip, entry);
+    }
+
+    fn spawn_lookup(self: &Arc<Self>, ip: IpAddr) {
+        // Enrichment runs on the blocking pool, which still carries the runtime handle
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if !self.pending.lock().unwrap().insert(ip) {
+            return;
+        }
+
+        let resolver = self.clone();
+        runtime.spawn(async move {
+            let hostname = match resolver.limit.clone().acquire_owned().await {
+                Ok(permit) => {
+                    // The permit lives as long as the blocking call so the limit is real
+                    let lookup = tokio::task::spawn_blocking(move || {
+                        let _permit = permit;
+                        system_reverse_lookup(ip)
+                    });
+                    tokio::time::timeout(resolver.lookup_timeout, lookup)
+                        .await
+                        .ok()
+                        .and_then(Result::ok)
+                        .flatten()
+                }
+                Err(_) => None,
+            };
+
+            let ttl = if hostname.is_some() { resolver.ttl } else { resolver.negative_ttl };
+            resolver.cache_insert(
+                ip,
+                CachedName {
+                    hostname,
+                    expires: Instant::now() + ttl,
+                },
+            );
+            resolver.pending.lock().unwrap().remove(&ip);
+        });
+    }
+}
+
+/// Fills `remote_hostname` through a shared `ReverseDnsResolver` when it is enabled
+pub struct ReverseDnsEnricher {
+    resolver: Arc<ReverseDnsResolver>,
+}
+
+impl ReverseDnsEnricher {
+    pub fn new(resolver: Arc<ReverseDnsResolver>) -> Self {
+        Self { resolver }
+    }
+}
+
+impl Enricher for ReverseDnsEnricher {
+    fn name(&self) -> &'static str {
+        "reverse-dns"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        if !self.resolver.is_enabled() {
+            return;
+        }
+        self.resolver.refresh_hosts();
+        for conn in connections.iter_mut() {
+            let Ok(ip) = conn.remote_address.parse::<IpAddr>() else {
+                continue;
+            };
+            if ip.is_unspecified() {
+                continue;
+            }
+            conn.remote_hostname = self.resolver.resolve_cached(ip);
+        }
+    }
+}
+
+fn hosts_file_path() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        let root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+        PathBuf::from(root).join("System32\\drivers\\etc\\hosts")
+    } else {
+        PathBuf::from("/etc/hosts")
+    }
+}
+
+/// Parse hosts(5): `address canonical_name [aliases...]`; the first entry for an address wins
+pub fn parse_hosts(content: &str) -> HashMap<IpAddr, String> {
+    let mut names = HashMap::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let (Some(address), Some(name)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(ip) = address.parse::<IpAddr>() {
+            names.entry(ip).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+/// PTR lookup through the system resolver (getnameinfo with NI_NAMEREQD)
+#[cfg(unix)]
+fn system_reverse_lookup(ip: IpAddr) -> Option<String> {
+    use std::mem::size_of;
+
+    // SAFETY: zeroed sockaddr_storage is a valid "unspecified" address we fill below
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let length = match ip {
+        IpAddr::V4(v4) => {
+            // SAFETY: sockaddr_storage is large and aligned enough for sockaddr_in
+            let addr = unsafe { &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in) };
+            addr.sin_family = libc::AF_INET as libc::sa_family_t;
+            addr.sin_addr.s_addr = u32::from_ne_bytes(v4.octets());
+            size_of::<libc::sockaddr_in>()
+        }
+        IpAddr::V6(v6) => {
+            // SAFETY: sockaddr_storage is large and aligned enough for sockaddr_in6
+            let addr = unsafe { &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6) };
+            addr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            addr.sin6_addr.s6_addr = v6.octets();
+            size_of::<libc::sockaddr_in6>()
+        }
+    };
+
+    let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+    // SAFETY: address and output buffer are valid for the lengths passed
+    let result = unsafe {
+        libc::getnameinfo(
+            &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+            length as libc::socklen_t,
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+
+    // SAFETY: getnameinfo NUL-terminates the host buffer on success
+    let name = unsafe { std::ffi::CStr::from_ptr(host.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Only the hosts file is consulted on platforms without getnameinfo bindings
+#[cfg(not(unix))]
+fn system_reverse_lookup(_ip: IpAddr) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hosts() {
+        let names = parse_hosts("127.0.0.1\tlocalhost\n# 10.0.0.1 commented\n10.0.0.2 db.internal db  # primary\n::1 ip6-localhost\n127.0.0.1 other\n");
+        assert_eq!(names.get(&"127.0.0.1".parse().unwrap()).map(String::as_str), Some("localhost"));
+        assert_eq!(names.get(&"10.0.0.2".parse().unwrap()).map(String::as_str), Some("db.internal"));
+        assert_eq!(names.get(&"::1".parse().unwrap()).map(String::as_str), Some("ip6-localhost"));
+        assert!(!names.contains_key(&"10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_enricher_uses_hosts_file_without_network() {
+        let hosts = std::env::temp_dir().join(format!("portviewer-hosts-{}", std::process::id()));
+        fs::write(&hosts, "10.9.8.7 build-cache.lan\n").unwrap();
+
+        let resolver = Arc::new(ReverseDnsResolver::with_hosts_path(&hosts));
+        let enricher = ReverseDnsEnricher::new(resolver.clone());
+        let mut connections = vec![ConnectionInfo::new(
+            crate::models::Protocol::TCP,
+            "10.0.0.1".to_string(),
+            51000,
+            "10.9.8.7".to_string(),
+            443,
+            crate::models::ConnectionState::Established,
+            1,
+            "curl".to_string(),
+        )];
+
+        enricher.enrich(&mut connections);
+        assert_eq!(connections[0].remote_hostname, None, "disabled resolver must not touch rows");
+
+        resolver.set_enabled(true);
+        enricher.enrich(&mut connections);
+        assert_eq!(connections[0].remote_hostname.as_deref(), Some("build-cache.lan"));
+
+        fs::remove_file(&hosts).unwrap();
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let mut resolver = ReverseDnsResolver::with_hosts_path("/nonexistent/hosts");
+        resolver.max_cache_entries = 2;
+        let entry = |secs: u64| CachedName {
+            hostname: None,
+            expires: Instant::now() + Duration::from_secs(secs),
+        };
+        let ip = |last: u8| IpAddr::from([10, 0, 0, last]);
+
+        resolver.cache.lock().unwrap().insert(ip(1), CachedName { hostname: None, expires: Instant::now() });
+        resolver.cache_insert(ip(2), entry(60));
+        // Full: the expired entry goes first
+        resolver.cache_insert(ip(3), entry(30));
+        assert!(!resolver.cache.lock().unwrap().contains_key(&ip(1)));
+        // Still full: the entry expiring soonest goes
+        resolver.cache_insert(ip(4), entry(90));
+        let cache = resolver.cache.lock().unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains_key(&ip(2)) && cache.contains_key(&ip(4)));
+    }
+}
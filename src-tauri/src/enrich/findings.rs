@@ -0,0 +1,158 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, Finding, FindingKind, Protocol, Severity};
+
+/// Ports associated with backdoors and IRC botnets rather than real services
+const SUSPICIOUS_PORTS: [(u16, &str); 2] = [(4444, "Metasploit's default handler port"), (6667, "IRC, common for botnet control")];
+
+/// Listeners above this port owned by a shell or interpreter look like a reverse or bind shell
+const HIGH_PORT: u16 = 1024;
+
+/// Shells almost never listen themselves; one that does is likely a bind shell
+const SHELLS: [&str; 13] = [
+    "sh", "bash", "dash", "zsh", "ksh", "mksh", "fish", "csh", "tcsh", "busybox", "pwsh", "powershell", "cmd",
+];
+
+/// Interpreters listen legitimately (dev servers), so these rate lower;
+/// versioned names (`python3.12`) match by prefix
+const INTERPRETERS: [&str; 7] = ["python", "perl", "ruby", "php", "node", "lua", "tclsh"];
+
+/// World-writable directories nothing legitimate should be executed from
+const TEMP_DIRS: [&str; 3] = ["/tmp/", "/var/tmp/", "/dev/shm/"];
+
+/// AppImages run from a FUSE mount under /tmp
+const APPIMAGE_MOUNT: &str = "/tmp/.mount_";
+
+/// Suffix the kernel appends to `/proc/<pid>/exe` once the file is unlinked
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// Program name without directory or `.exe`, lowercased
+fn program_name(conn: &ConnectionInfo) -> String {
+    let exe = conn.context.exe.as_deref().map(|exe| exe.trim_end_matches(DELETED_SUFFIX));
+    let path = exe.unwrap_or(&conn.process_name);
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+fn matches_program(list: &[&str], name: &str) -> bool {
+    list.iter().any(|interpreter| {
+        name.strip_prefix(interpreter)
+            .is_some_and(|rest| rest.is_empty() || rest.chars().all(|c| c.is_ascii_digit() || c == '.'))
+    })
+}
+
+/// Heuristic findings for one connection, most severe first
+pub fn evaluate(conn: &ConnectionInfo) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if let Some(exe) = &conn.context.exe {
+        if let Some(path) = exe.strip_suffix(DELETED_SUFFIX) {
+            findings.push(Finding {
+                kind: FindingKind::DeletedExecutable,
+                severity: Severity::High,
+                message: format!("{} runs from {}, which no longer exists on disk", conn.process_name, path),
+            });
+        } else if TEMP_DIRS.iter().any(|dir| exe.starts_with(dir)) && !exe.starts_with(APPIMAGE_MOUNT) {
+            findings.push(Finding {
+                kind: FindingKind::TempExecutable,
+                severity: Severity::High,
+                message: format!("{} runs from a temporary directory: {}", conn.process_name, exe),
+            });
+        }
+    }
+
+    if matches!(conn.protocol, Protocol::Unix(_)) {
+        return findings;
+    }
+
+    let name = program_name(conn);
+    if conn.state.is_listening() && conn.local_port > HIGH_PORT {
+        let severity = if matches_program(&SHELLS, &name) {
+            Some((Severity::Medium, "shell"))
+        } else if matches_program(&INTERPRETERS, &name) {
+            Some((Severity::Low, "interpreter"))
+        } else {
+            None
+        };
+        if let Some((severity, what)) = severity {
+            findings.push(Finding {
+                kind: FindingKind::InterpreterListener,
+                severity,
+                message: format!("{} (a {}) listens on port {}", name, what, conn.local_port),
+            });
+        }
+    }
+
+    let port = if conn.state.is_listening() { conn.local_port } else { conn.remote_port };
+    if let Some((_, reason)) = SUSPICIOUS_PORTS.iter().find(|(bad, _)| *bad == port) {
+        let action = if conn.state.is_listening() { "listens on" } else { "connects to" };
+        findings.push(Finding {
+            kind: FindingKind::SuspiciousPort,
+            severity: Severity::Medium,
+            message: format!("{} {} port {} ({})", conn.process_name, action, port, reason),
+        });
+    }
+
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+    findings
+}
+
+/// Flags rows matching the suspicious-connection heuristics
+///
+/// Runs after the container enricher, which reads the executable path the
+/// deleted-binary and temp-directory checks rely on.
+pub struct FindingsEnricher;
+
+impl FindingsEnricher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Enricher for FindingsEnricher {
+    fn name(&self) -> &'static str {
+        "findings"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        for conn in connections.iter_mut() {
+            conn.findings = evaluate(conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    fn conn(process: &str, exe: Option<&str>, local_port: u16, remote_port: u16, state: ConnectionState) -> ConnectionInfo {
+        let remote = if remote_port == 0 { "0.0.0.0" } else { "203.0.113.7" };
+        let mut conn = ConnectionInfo::new(Protocol::TCP, "0.0.0.0".to_string(), local_port, remote.to_string(), remote_port, state, 42, process.to_string());
+        conn.context.exe = exe.map(str::to_string);
+        conn
+    }
+
+    fn kinds(conn: &ConnectionInfo) -> Vec<FindingKind> {
+        evaluate(conn).into_iter().map(|finding| finding.kind).collect()
+    }
+
+    #[test]
+    fn test_heuristics() {
+        let deleted = conn("updater", Some("/usr/lib/updater/updater (deleted)"), 50123, 443, ConnectionState::Established);
+        assert_eq!(kinds(&deleted), vec![FindingKind::DeletedExecutable]);
+
+        let dropper = conn(".x", Some("/tmp/.x"), 40000, 6667, ConnectionState::Established);
+        assert_eq!(kinds(&dropper), vec![FindingKind::TempExecutable, FindingKind::SuspiciousPort]);
+
+        let bind_shell = conn("python3", Some("/usr/bin/python3.12"), 4444, 0, ConnectionState::Listen);
+        assert_eq!(kinds(&bind_shell), vec![FindingKind::SuspiciousPort, FindingKind::InterpreterListener]);
+
+        // Privileged ports, ordinary daemons and look-alike names stay quiet
+        assert!(evaluate(&conn("python3", Some("/usr/bin/python3"), 80, 0, ConnectionState::Listen)).is_empty());
+        assert!(evaluate(&conn("nginx", Some("/usr/sbin/nginx"), 8080, 0, ConnectionState::Listen)).is_empty());
+        assert!(evaluate(&conn("shellcheck", None, 9000, 0, ConnectionState::Listen)).is_empty());
+        assert!(evaluate(&conn("app", Some("/tmp/.mount_appXyz/usr/bin/app"), 50300, 443, ConnectionState::Established)).is_empty());
+        assert_eq!(evaluate(&conn("bash", Some("/usr/bin/bash"), 31337, 0, ConnectionState::Listen))[0].severity, Severity::Medium);
+        assert!(evaluate(&conn("node", Some("/usr/bin/node"), 50200, 3000, ConnectionState::Established)).is_empty());
+    }
+}
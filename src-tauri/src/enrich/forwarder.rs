@@ -0,0 +1,387 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, ForwardTarget};
+use std::collections::HashMap;
+use std::fs;
+
+/// Programs whose listeners relay traffic to somewhere else
+const FORWARDERS: [&str; 4] = ["docker-proxy", "kubectl", "ssh", "socat"];
+
+/// kubectl global and port-forward flags that consume the next argument
+const KUBECTL_VALUE_FLAGS: [&str; 13] = [
+    "-n",
+    "--namespace",
+    "--address",
+    "--context",
+    "--cluster",
+    "--kubeconfig",
+    "--user",
+    "--pod-running-timeout",
+    "-s",
+    "--server",
+    "--token",
+    "--as",
+    "-v",
+];
+
+/// ssh options that take an argument (ssh(1) synopsis)
+const SSH_VALUE_FLAGS: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+/// One forwarding rule parsed from a forwarder's command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forward {
+    /// Local listen port, `None` when chosen at random (`kubectl port-forward svc/db :5432`)
+    pub listen_port: Option<u16>,
+    pub target: ForwardTarget,
+}
+
+/// Resolves listeners owned by docker-proxy, `kubectl port-forward`, `ssh -L`
+/// and socat to the address they forward to, read from `/proc/<pid>/cmdline`
+pub struct ForwarderEnricher;
+
+impl ForwarderEnricher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Enricher for ForwarderEnricher {
+    fn name(&self) -> &'static str {
+        "forwarder"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let mut forwards: HashMap<u32, Vec<Forward>> = HashMap::new();
+
+        for conn in connections.iter_mut() {
+            if conn.pid == 0
+                || !conn.state.is_listening()
+                || !FORWARDERS.contains(&conn.process_name.as_str())
+            {
+                continue;
+            }
+
+            let rules = forwards
+                .entry(conn.pid)
+                .or_insert_with(|| read_cmdline(conn.pid).map(|argv| parse_forwards(&argv)).unwrap_or_default());
+            conn.forward = match_forward(rules, conn.local_port);
+        }
+    }
+}
+
+fn read_cmdline(pid: u32) -> Option<Vec<String>> {
+    let raw = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    Some(
+        raw.split(|byte| *byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+    )
+}
+
+/// Pick the rule for a listener; a single random-port rule matches any port
+fn match_forward(rules: &[Forward], port: u16) -> Option<ForwardTarget> {
+    rules
+        .iter()
+        .find(|rule| rule.listen_port == Some(port))
+        .or_else(|| match rules {
+            [only] if only.listen_port.is_none() => Some(only),
+            _ => None,
+        })
+        .map(|rule| rule.target.clone())
+}
+
+/// Parse the forwarding rules out of a full argv (program name first)
+pub fn parse_forwards(argv: &[String]) -> Vec<Forward> {
+    let Some((program, args)) = argv.split_first() else {
+        return Vec::new();
+    };
+
+    match program.rsplit('/').next().unwrap_or(program) {
+        "docker-proxy" => parse_docker_proxy(args),
+        "kubectl" => parse_kubectl(args),
+        "ssh" => parse_ssh(args),
+        "socat" => parse_socat(args),
+        _ => Vec::new(),
+    }
+}
+
+/// `docker-proxy -proto tcp -host-ip 0.0.0.0 -host-port 5432 -container-ip 172.17.0.2 -container-port 5432`
+fn parse_docker_proxy(args: &[String]) -> Vec<Forward> {
+    let flags = go_flags(args);
+    let Some(container_ip) = flags.get("container-ip") else {
+        return Vec::new();
+    };
+
+    vec![Forward {
+        listen_port: flags.get("host-port").and_then(|port| port.parse().ok()),
+        target: ForwardTarget {
+            forwarder: "docker-proxy".to_string(),
+            host: container_ip.clone(),
+            port: flags.get("container-port").and_then(|port| port.parse().ok()),
+        },
+    }]
+}
+
+/// Go `flag` package syntax: `-name value`, `-name=value` or `--name=value`
+fn go_flags(args: &[String]) -> HashMap<String, String> {
+    let mut flags = HashMap::new();
+    let mut iter = args.iter().peekable();
+
+    while let Some(arg) = iter.next() {
+        let Some(name) = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) else {
+            continue;
+        };
+        if let Some((name, value)) = name.split_once('=') {
+            flags.insert(name.to_string(), value.to_string());
+        } else if let Some(value) = iter.next_if(|next| !next.starts_with('-')) {
+            flags.insert(name.to_string(), value.clone());
+        }
+    }
+    flags
+}
+
+/// `kubectl [-n ns] port-forward [--address ...] TYPE/NAME [LOCAL:]REMOTE ...`
+fn parse_kubectl(args: &[String]) -> Vec<Forward> {
+    let mut namespace = None;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with('-') {
+            positional.push(arg.as_str());
+            continue;
+        }
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None if KUBECTL_VALUE_FLAGS.contains(&arg.as_str()) => (arg.as_str(), iter.next().cloned()),
+            // Short form with the value attached: -nprod
+            None if arg.starts_with("-n") && !arg.starts_with("--") => ("-n", Some(arg[2..].to_string())),
+            None => continue,
+        };
+        if flag == "-n" || flag == "--namespace" {
+            namespace = value;
+        }
+    }
+
+    let Some(start) = positional.iter().position(|arg| *arg == "port-forward") else {
+        return Vec::new();
+    };
+    let Some((resource, ports)) = positional[start + 1..].split_first() else {
+        return Vec::new();
+    };
+    let host = match &namespace {
+        Some(namespace) => format!("{}/{}", namespace, resource),
+        None => resource.to_string(),
+    };
+
+    ports
+        .iter()
+        .filter_map(|spec| {
+            let (local, remote) = spec.split_once(':').unwrap_or((spec, spec));
+            Some(Forward {
+                listen_port: if local.is_empty() { None } else { Some(local.parse().ok()?) },
+                target: ForwardTarget {
+                    forwarder: "kubectl".to_string(),
+                    host: host.clone(),
+                    port: Some(remote.parse().ok()?),
+                },
+            })
+        })
+        .collect()
+}
+
+/// `ssh [-fN] -L [bind:]port:host:hostport ... destination [command]`
+fn parse_ssh(args: &[String]) -> Vec<Forward> {
+    let mut specs = Vec::new();
+    let mut index = 0;
+
+    while index < args.len() {
+        let Some(options) = args[index].strip_prefix('-').filter(|options| !options.is_empty()) else {
+            // The destination ends option parsing; the rest is the remote command
+            break;
+        };
+        // Options may be bundled (-fNL 8080:db:5432) and values attached (-L8080:db:5432)
+        for (offset, option) in options.char_indices() {
+            if !SSH_VALUE_FLAGS.contains(option) {
+                continue;
+            }
+            let attached = &options[offset + option.len_utf8()..];
+            let value = if attached.is_empty() {
+                index += 1;
+                args.get(index).map(String::as_str).unwrap_or_default()
+            } else {
+                attached
+            };
+
+            if option == 'L' {
+                specs.push(value.to_string());
+            } else if option == 'o' {
+                // -o LocalForward=8080 db:5432 / -o "LocalForward 8080 db:5432"
+                let (key, rest) = value.split_once(['=', ' ']).unwrap_or((value, ""));
+                if key.eq_ignore_ascii_case("LocalForward") {
+                    specs.push(rest.split_whitespace().collect::<Vec<_>>().join(":"));
+                }
+            }
+            break;
+        }
+        index += 1;
+    }
+
+    specs.iter().filter_map(|spec| parse_ssh_forward(spec)).collect()
+}
+
+fn parse_ssh_forward(spec: &str) -> Option<Forward> {
+    let parts = split_host_port_list(spec);
+    // Drop the optional bind address; what remains is port:host:hostport or port:/socket
+    let parts = match parts.len() {
+        4 => &parts[1..],
+        3 if parts[2].starts_with('/') => &parts[1..],
+        _ => &parts[..],
+    };
+
+    let (host, port) = match parts {
+        [_, path] if path.starts_with('/') => (path.clone(), None),
+        [_, host, port] => (host.clone(), Some(port.parse().ok()?)),
+        _ => return None,
+    };
+
+    Some(Forward {
+        listen_port: Some(parts[0].parse().ok()?),
+        target: ForwardTarget {
+            forwarder: "ssh".to_string(),
+            host,
+            port,
+        },
+    })
+}
+
+/// `socat TCP-LISTEN:8080,fork,reuseaddr TCP:10.0.0.5:80`
+fn parse_socat(args: &[String]) -> Vec<Forward> {
+    // Addresses always contain a colon; option values (-T 10, -d) never do
+    let addresses: Vec<&str> = args
+        .iter()
+        .filter(|arg| !arg.starts_with('-') && arg.contains(':'))
+        .map(String::as_str)
+        .collect();
+    let [first, second] = addresses[..] else {
+        return Vec::new();
+    };
+    let (listen, target) = if is_socat_listener(first) {
+        (first, second)
+    } else if is_socat_listener(second) {
+        (second, first)
+    } else {
+        return Vec::new();
+    };
+
+    let Some((_, listen_spec)) = listen.split_once(':') else {
+        return Vec::new();
+    };
+    let Ok(listen_port) = listen_spec.split(',').next().unwrap_or_default().parse() else {
+        return Vec::new();
+    };
+
+    let (kind, target_spec) = target.split_once(':').unwrap_or((target, ""));
+    let target_spec = target_spec.split(',').next().unwrap_or_default();
+    let kind = kind.to_uppercase();
+    let (host, port) = if kind.starts_with("UNIX") {
+        (target_spec.to_string(), None)
+    } else {
+        match &split_host_port_list(target_spec)[..] {
+            [host, port] => (host.clone(), port.parse().ok()),
+            _ => (target.to_string(), None),
+        }
+    };
+
+    vec![Forward {
+        listen_port: Some(listen_port),
+        target: ForwardTarget {
+            forwarder: "socat".to_string(),
+            host,
+            port,
+        },
+    }]
+}
+
+fn is_socat_listener(address: &str) -> bool {
+    let kind = address.split(':').next().unwrap_or_default().to_uppercase();
+    kind.ends_with("-LISTEN") || kind.ends_with("-L")
+}
+
+/// Split on `:` except inside `[...]`, dropping the brackets (`[::1]:80` → `::1`, `80`)
+fn split_host_port_list(spec: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut bracketed = false;
+
+    for c in spec.chars() {
+        match c {
+            '[' => bracketed = true,
+            ']' => bracketed = false,
+            ':' if !bracketed => parts.push(String::new()),
+            _ => parts.last_mut().expect("parts is never empty").push(c),
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn target(forwarder: &str, host: &str, port: Option<u16>) -> ForwardTarget {
+        ForwardTarget {
+            forwarder: forwarder.to_string(),
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn test_parse_docker_proxy() {
+        let rules = parse_forwards(&argv(
+            "/usr/bin/docker-proxy -proto tcp -host-ip 0.0.0.0 -host-port 5432 -container-ip 172.17.0.2 -container-port 5432",
+        ));
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].listen_port, Some(5432));
+        assert_eq!(rules[0].target, target("docker-proxy", "172.17.0.2", Some(5432)));
+    }
+
+    #[test]
+    fn test_parse_kubectl_port_forward() {
+        let rules = parse_forwards(&argv("kubectl -n prod port-forward --address 0.0.0.0 svc/db 15432:5432 :6379"));
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].listen_port, Some(15432));
+        assert_eq!(rules[0].target, target("kubectl", "prod/svc/db", Some(5432)));
+        assert_eq!(rules[1].listen_port, None);
+
+        assert_eq!(match_forward(&rules, 15432).and_then(|t| t.port), Some(5432));
+        assert_eq!(match_forward(&rules, 40000), None);
+    }
+
+    #[test]
+    fn test_parse_ssh_local_forwards() {
+        let rules = parse_forwards(&argv(
+            "ssh -fNL 8080:intranet:80 -L 127.0.0.1:9000:[fd00::5]:9000 -L8022:/run/app.sock bastion -L 1:x:1",
+        ));
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].target, target("ssh", "intranet", Some(80)));
+        assert_eq!(rules[1].listen_port, Some(9000));
+        assert_eq!(rules[1].target, target("ssh", "fd00::5", Some(9000)));
+        assert_eq!(rules[2].target, target("ssh", "/run/app.sock", None));
+    }
+
+    #[test]
+    fn test_parse_socat() {
+        let rules = parse_forwards(&argv("socat -d -d TCP4-LISTEN:8080,fork,reuseaddr TCP:10.0.0.5:80"));
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].listen_port, Some(8080));
+        assert_eq!(rules[0].target, target("socat", "10.0.0.5", Some(80)));
+
+        let rules = parse_forwards(&argv("socat UNIX-CONNECT:/var/run/docker.sock TCP-LISTEN:2375,fork"));
+        assert_eq!(rules[0].target, target("socat", "/var/run/docker.sock", None));
+    }
+}
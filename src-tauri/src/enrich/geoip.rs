@@ -0,0 +1,413 @@
+use crate::enrich::mmdb::{MaxMindDb, Value};
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, PeerInfo};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv6Addr};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// What is known about a network from one database
+#[derive(Debug, Clone, Default, PartialEq)]
+struct NetworkInfo {
+    asn: Option<u32>,
+    org: Option<String>,
+    country: Option<String>,
+}
+
+impl NetworkInfo {
+    /// Read the fields GeoLite2 (ASN, Country, City), DB-IP and ipinfo databases use
+    fn from_mmdb(record: &Value) -> Self {
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| record.get(key).and_then(Value::as_str))
+                .map(str::to_string)
+        };
+        let asn = record
+            .get("autonomous_system_number")
+            .and_then(Value::as_u64)
+            .map(|asn| asn as u32)
+            .or_else(|| record.get("asn").and_then(Value::as_str).and_then(parse_asn));
+        let country = ["country", "registered_country"].iter().find_map(|key| match record.get(key) {
+            Some(Value::String(code)) => Some(code.clone()),
+            Some(map) => map.get("iso_code").and_then(Value::as_str).map(str::to_string),
+            None => None,
+        });
+        Self {
+            asn,
+            org: text(&["autonomous_system_organization", "as_name", "name", "organization"]),
+            country: country.or_else(|| text(&["country_code"])),
+        }
+    }
+}
+
+/// `AS15169` or `15169`
+fn parse_asn(value: &str) -> Option<u32> {
+    let value = value.trim();
+    value
+        .strip_prefix("AS")
+        .or_else(|| value.strip_prefix("as"))
+        .unwrap_or(value)
+        .parse()
+        .ok()
+}
+
+/// Networks from a CSV file, as ranges sorted by start
+///
+/// Addresses are kept as IPv6 (IPv4 mapped) so both families share one table.
+/// Blocks may nest (a /16 and a /24 inside it); the most specific one wins.
+struct CsvTable {
+    /// Sorted by start, then largest first, so nested blocks follow their parent
+    ranges: Vec<(u128, u128, NetworkInfo)>,
+    /// `reach[i]` is the highest end among `ranges[..=i]`
+    reach: Vec<u128>,
+}
+
+impl CsvTable {
+    /// Parse a CSV with a header row naming a `network` column (CIDR) and any
+    /// of `asn`/`autonomous_system_number`, `org`/`as_name`/
+    /// `autonomous_system_organization` and `country`/`country_code`;
+    /// the GeoLite2 ASN CSVs load as they are
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        let header: Vec<String> = split_csv_line(lines.next().ok_or("empty file")?)
+            .into_iter()
+            .map(|column| column.to_lowercase())
+            .collect();
+        let column = |names: &[&str]| header.iter().position(|column| names.contains(&column.as_str()));
+        let network = column(&["network", "cidr", "prefix"]).ok_or("no 'network' column in the header")?;
+        let asn = column(&["asn", "autonomous_system_number"]);
+        let org = column(&["org", "organization", "as_name", "autonomous_system_organization"]);
+        let country = column(&["country", "country_code", "country_iso_code"]);
+
+        let mut ranges = Vec::new();
+        for line in lines {
+            let fields = split_csv_line(line);
+            let field = |index: Option<usize>| {
+                index
+                    .and_then(|i| fields.get(i))
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+            // Malformed rows are skipped rather than failing the whole file
+            let Some((start, end)) = field(Some(network)).as_deref().and_then(network_range) else {
+                continue;
+            };
+            ranges.push((
+                start,
+                end,
+                NetworkInfo {
+                    asn: field(asn).as_deref().and_then(parse_asn),
+                    org: field(org),
+                    country: field(country),
+                },
+            ));
+        }
+        ranges.sort_by_key(|(start, end, _)| (*start, std::cmp::Reverse(*end)));
+        let reach = ranges
+            .iter()
+            .scan(0u128, |reach, (_, end, _)| {
+                *reach = (*reach).max(*end);
+                Some(*reach)
+            })
+            .collect();
+        Ok(Self { ranges, reach })
+    }
+
+    /// The innermost range holding `ip`: walk back from the last range
+    /// starting at or before it, until no earlier range reaches that far
+    fn lookup(&self, ip: IpAddr) -> Option<&NetworkInfo> {
+        let key = to_mapped(ip);
+        let index = self.ranges.partition_point(|(start, _, _)| *start <= key);
+        (0..index)
+            .rev()
+            .take_while(|i| self.reach[*i] >= key)
+            .find(|i| key <= self.ranges[*i].1)
+            .map(|i| &self.ranges[i].2)
+    }
+}
+
+/// Minimal CSV field splitting with double-quoted fields (org names contain commas)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn to_mapped(ip: IpAddr) -> u128 {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+/// First and last address of a CIDR block, IPv4 mapped into IPv6
+fn network_range(cidr: &str) -> Option<(u128, u128)> {
+    let (network, prefix) = crate::enrich::labels::parse_cidr(cidr)?;
+    let (start, host_bits) = match network {
+        IpAddr::V4(v4) => (u128::from(v4.to_ipv6_mapped()), 32 - prefix as u32),
+        IpAddr::V6(v6) => (u128::from(v6), 128 - prefix as u32),
+    };
+    let host_mask = if host_bits == 128 { u128::MAX } else { (1u128 << host_bits) - 1 };
+    Some((start & !host_mask, start | host_mask))
+}
+
+enum Database {
+    MaxMind(MaxMindDb),
+    Csv(CsvTable),
+}
+
+/// A database loaded from the user's configured path
+struct LoadedDatabase {
+    path: String,
+    database: Database,
+}
+
+/// What the UI shows about each configured database
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoIpDatabaseInfo {
+    pub path: String,
+    /// `mmdb` or `csv`
+    pub format: String,
+    /// mmdb `database_type`, or the number of CSV networks
+    pub description: String,
+}
+
+/// Errors from loading a GeoIP database
+#[derive(Debug, Clone)]
+pub enum GeoIpError {
+    Io(String),
+    Invalid(String),
+}
+
+impl fmt::Display for GeoIpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoIpError::Io(msg) => write!(f, "GeoIP database I/O error: {}", msg),
+            GeoIpError::Invalid(msg) => write!(f, "Invalid GeoIP database: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for GeoIpError {}
+
+/// The user's local ASN/GeoIP databases; never touches the network
+///
+/// Several databases can be configured (GeoLite2 ships ASN and Country
+/// separately); for each field the first database that knows it wins.
+pub struct GeoIpStore {
+    databases: RwLock<Vec<LoadedDatabase>>,
+}
+
+impl GeoIpStore {
+    pub fn new() -> Self {
+        Self {
+            databases: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Replace the databases with the files at `paths`, all or nothing
+    pub fn set_paths(&self, paths: &[String]) -> Result<Vec<GeoIpDatabaseInfo>, GeoIpError> {
+        let loaded = paths
+            .iter()
+            .map(|path| load_database(Path::new(path)).map(|database| LoadedDatabase { path: path.clone(), database }))
+            .collect::<Result<Vec<_>, _>>()?;
+        *self.databases.write().unwrap() = loaded;
+        Ok(self.databases())
+    }
+
+    pub fn databases(&self) -> Vec<GeoIpDatabaseInfo> {
+        self.databases
+            .read()
+            .unwrap()
+            .iter()
+            .map(|loaded| match &loaded.database {
+                Database::MaxMind(db) => GeoIpDatabaseInfo {
+                    path: loaded.path.clone(),
+                    format: "mmdb".to_string(),
+                    description: db.database_type.clone(),
+                },
+                Database::Csv(table) => GeoIpDatabaseInfo {
+                    path: loaded.path.clone(),
+                    format: "csv".to_string(),
+                    description: format!("{} networks", table.ranges.len()),
+                },
+            })
+            .collect()
+    }
+
+    /// Scope of `ip`, plus ASN, org and country for public addresses
+    pub fn lookup(&self, ip: IpAddr) -> PeerInfo {
+        let scope = address_scope(ip);
+        let mut peer = PeerInfo {
+            scope: scope.to_string(),
+            ..PeerInfo::default()
+        };
+        if scope != "public" {
+            return peer;
+        }
+        for loaded in self.databases.read().unwrap().iter() {
+            let info = match &loaded.database {
+                Database::MaxMind(db) => match db.lookup(ip) {
+                    Ok(record) => record.map(|record| NetworkInfo::from_mmdb(&record)),
+                    Err(e) => {
+                        eprintln!("GeoIP: lookup of {} in {} failed: {}", ip, loaded.path, e);
+                        None
+                    }
+                },
+                Database::Csv(table) => table.lookup(ip).cloned(),
+            };
+            if let Some(info) = info {
+                peer.asn = peer.asn.or(info.asn);
+                peer.org = peer.org.or(info.org);
+                peer.country = peer.country.or(info.country);
+            }
+        }
+        peer
+    }
+}
+
+fn load_database(path: &Path) -> Result<Database, GeoIpError> {
+    let io_error = |e: std::io::Error| GeoIpError::Io(format!("{}: {}", path.display(), e));
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        let content = fs::read_to_string(path).map_err(io_error)?;
+        CsvTable::parse(&content)
+            .map(Database::Csv)
+            .map_err(|e| GeoIpError::Invalid(format!("{}: {}", path.display(), e)))
+    } else {
+        let bytes = fs::read(path).map_err(io_error)?;
+        MaxMindDb::from_bytes(bytes)
+            .map(Database::MaxMind)
+            .map_err(|e| GeoIpError::Invalid(format!("{}: {}", path.display(), e)))
+    }
+}
+
+/// `loopback`, `private` (RFC 1918, IPv6 unique local), `shared` (RFC 6598
+/// carrier-grade NAT), `link_local`, `multicast` or `public`
+pub fn address_scope(ip: IpAddr) -> &'static str {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) if v4.is_loopback() => "loopback",
+        IpAddr::V4(v4) if v4.is_private() => "private",
+        IpAddr::V4(v4) if v4.octets()[0] == 100 && (v4.octets()[1] & 0xC0) == 64 => "shared",
+        IpAddr::V4(v4) if v4.is_link_local() => "link_local",
+        IpAddr::V4(v4) if v4.is_multicast() || v4.is_broadcast() => "multicast",
+        IpAddr::V6(v6) if v6.is_loopback() => "loopback",
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xFE00) == 0xFC00 => "private",
+        IpAddr::V6(v6) if (v6.segments()[0] & 0xFFC0) == 0xFE80 => "link_local",
+        IpAddr::V6(v6) if v6.is_multicast() => "multicast",
+        _ => "public",
+    }
+}
+
+/// Annotates `remote_peer` from the local databases
+pub struct GeoIpEnricher {
+    store: Arc<GeoIpStore>,
+}
+
+impl GeoIpEnricher {
+    pub fn new(store: Arc<GeoIpStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl Enricher for GeoIpEnricher {
+    fn name(&self) -> &'static str {
+        "geoip"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let mut cache: HashMap<IpAddr, PeerInfo> = HashMap::new();
+        for conn in connections.iter_mut() {
+            if conn.remote_port == 0 {
+                continue;
+            }
+            let Ok(ip) = conn.remote_address.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() else {
+                continue;
+            };
+            if ip.is_unspecified() || ip == IpAddr::V6(Ipv6Addr::UNSPECIFIED) {
+                continue;
+            }
+            conn.remote_peer = Some(cache.entry(ip).or_insert_with(|| self.store.lookup(ip)).clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_lookup_and_scopes() {
+        let csv = "\
+network,autonomous_system_number,autonomous_system_organization,country
+1.0.0.0/24,13335,\"Cloudflare, Inc.\",AU
+8.8.8.0/24,15169,GOOGLE,US
+2001:4860::/32,AS15169,GOOGLE,US
+not-a-network,1,broken,XX
+";
+        let dir = std::env::temp_dir().join(format!("portviewer-geoip-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("asn.csv");
+        fs::write(&path, csv).unwrap();
+
+        let store = GeoIpStore::new();
+        let info = store.set_paths(&[path.to_string_lossy().into_owned()]).unwrap();
+        assert_eq!(info[0].description, "3 networks");
+
+        let google = store.lookup("8.8.8.8".parse().unwrap());
+        assert_eq!((google.scope.as_str(), google.asn, google.country.as_deref()), ("public", Some(15169), Some("US")));
+        assert_eq!(store.lookup("1.0.0.1".parse().unwrap()).org.as_deref(), Some("Cloudflare, Inc."));
+        assert_eq!(store.lookup("2001:4860:4860::8888".parse().unwrap()).asn, Some(15169));
+        assert_eq!(store.lookup("9.9.9.9".parse().unwrap()).asn, None);
+
+        assert_eq!(store.lookup("192.168.1.1".parse().unwrap()).scope, "private");
+        assert_eq!(store.lookup("::ffff:10.0.0.1".parse().unwrap()).scope, "private");
+        assert_eq!(store.lookup("127.0.0.1".parse().unwrap()).scope, "loopback");
+        assert_eq!(store.lookup("fd12::1".parse().unwrap()).scope, "private");
+        assert_eq!(store.lookup("100.64.0.1".parse().unwrap()).scope, "shared");
+
+        assert!(store.set_paths(&[dir.join("missing.mmdb").to_string_lossy().into_owned()]).is_err());
+        // A failed load keeps the previous databases
+        assert_eq!(store.databases().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_csv_nested_networks() {
+        let table = CsvTable::parse(
+            "\
+network,asn
+10.0.0.0/8,1
+10.1.0.0/16,2
+10.1.2.0/24,3
+10.1.0.0/24,4
+",
+        )
+        .unwrap();
+        let asn = |ip: &str| table.lookup(ip.parse().unwrap()).and_then(|info| info.asn);
+        assert_eq!(asn("10.1.2.3"), Some(3));
+        assert_eq!(asn("10.1.0.9"), Some(4));
+        assert_eq!(asn("10.1.9.9"), Some(2));
+        assert_eq!(asn("10.200.0.1"), Some(1));
+        assert_eq!(asn("11.0.0.1"), None);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, InterfaceInfo, InterfaceKind, Protocol};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+
+/// A local interface with every address assigned to it
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkInterface {
+    #[serde(flatten)]
+    pub info: InterfaceInfo,
+    pub addresses: Vec<IpAddr>,
+    /// Netmask of each address as a prefix length, in the same order
+    pub prefix_lengths: Vec<u8>,
+}
+
+/// Local interfaces and their addresses, as `getifaddrs(3)` reports them
+#[cfg(unix)]
+pub fn list_interfaces() -> io::Result<Vec<NetworkInterface>> {
+    use std::ffi::CStr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    // getifaddrs returns one entry per address, plus an AF_PACKET/AF_LINK
+    // entry per interface; collect them in first-seen order
+    let mut interfaces: Vec<(String, u32, Vec<IpAddr>, Vec<u8>)> = Vec::new();
+    let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs allocates the list, which is only read before freeifaddrs
+    unsafe {
+        if libc::getifaddrs(&mut head) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut cursor = head;
+        while let Some(entry) = cursor.as_ref() {
+            cursor = entry.ifa_next;
+            if entry.ifa_name.is_null() {
+                continue;
+            }
+            let name = CStr::from_ptr(entry.ifa_name).to_string_lossy().into_owned();
+            let address = match entry.ifa_addr.as_ref().map(|addr| addr.sa_family as i32) {
+                Some(libc::AF_INET) => {
+                    let addr = &*(entry.ifa_addr as *const libc::sockaddr_in);
+                    let prefix = netmask_prefix(entry.ifa_netmask, libc::AF_INET).unwrap_or(32);
+                    Some((IpAddr::V4(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr))), prefix))
+                }
+                Some(libc::AF_INET6) => {
+                    let addr = &*(entry.ifa_addr as *const libc::sockaddr_in6);
+                    let prefix = netmask_prefix(entry.ifa_netmask, libc::AF_INET6).unwrap_or(128);
+                    Some((IpAddr::V6(Ipv6Addr::from(addr.sin6_addr.s6_addr)), prefix))
+                }
+                _ => None,
+            };
+            let index = match interfaces.iter().position(|(known, _, _, _)| *known == name) {
+                Some(index) => index,
+                None => {
+                    interfaces.push((name, entry.ifa_flags, Vec::new(), Vec::new()));
+                    interfaces.len() - 1
+                }
+            };
+            if let Some((address, prefix)) = address {
+                interfaces[index].2.push(address);
+                interfaces[index].3.push(prefix);
+            }
+        }
+        libc::freeifaddrs(head);
+    }
+
+    Ok(interfaces
+        .into_iter()
+        .map(|(name, flags, addresses, prefix_lengths)| NetworkInterface {
+            info: InterfaceInfo {
+                kind: classify(&name, flags),
+                flags: flag_names(flags),
+                name,
+            },
+            addresses,
+            prefix_lengths,
+        })
+        .collect())
+}
+
+/// Prefix length of a `getifaddrs` netmask for an address of `family`
+///
+/// On macOS and the BSDs netmasks are often shorter than their sockaddr type
+/// (trailing zero bytes are left out) and may carry no family, so only
+/// `sa_len` bytes are read. Elsewhere the family must match.
+///
+/// # Safety
+/// `mask` must be null or point to a sockaddr from `getifaddrs`.
+#[cfg(unix)]
+unsafe fn netmask_prefix(mask: *const libc::sockaddr, family: i32) -> Option<u8> {
+    if mask.is_null() {
+        return None;
+    }
+    let (offset, len) = match family {
+        libc::AF_INET => (std::mem::offset_of!(libc::sockaddr_in, sin_addr), 4),
+        libc::AF_INET6 => (std::mem::offset_of!(libc::sockaddr_in6, sin6_addr), 16),
+        _ => return None,
+    };
+    let mask_family = std::ptr::addr_of!((*mask).sa_family).read() as i32;
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    let available = {
+        if mask_family != family && mask_family != libc::AF_UNSPEC {
+            return None;
+        }
+        std::ptr::addr_of!((*mask).sa_len).read() as usize
+    };
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    let available = {
+        if mask_family != family {
+            return None;
+        }
+        offset + len
+    };
+
+    let mut bytes = [0u8; 16];
+    let readable = available.saturating_sub(offset).min(len);
+    std::ptr::copy_nonoverlapping((mask as *const u8).add(offset), bytes.as_mut_ptr(), readable);
+    Some(bytes[..len].iter().map(|byte| byte.count_ones()).sum::<u32>() as u8)
+}
+
+/// Not implemented on Windows yet; sockets simply carry no interface
+#[cfg(not(unix))]
+pub fn list_interfaces() -> io::Result<Vec<NetworkInterface>> {
+    Ok(Vec::new())
+}
+
+#[cfg(unix)]
+fn flag_names(flags: u32) -> Vec<String> {
+    [
+        (libc::IFF_UP, "up"),
+        (libc::IFF_RUNNING, "running"),
+        (libc::IFF_LOOPBACK, "loopback"),
+        (libc::IFF_POINTOPOINT, "pointopoint"),
+        (libc::IFF_BROADCAST, "broadcast"),
+        (libc::IFF_MULTICAST, "multicast"),
+        (libc::IFF_PROMISC, "promisc"),
+    ]
+    .iter()
+    .filter(|(flag, _)| flags & *flag as u32 != 0)
+    .map(|(_, name)| name.to_string())
+    .collect()
+}
+
+/// Link type from `/sys/class/net/<name>` (ARPHRD type and the wireless,
+/// tun_flags and bridge entries), falling back to the name on other systems
+#[cfg(unix)]
+fn classify(name: &str, flags: u32) -> InterfaceKind {
+    if flags & libc::IFF_LOOPBACK as u32 != 0 {
+        return InterfaceKind::Loopback;
+    }
+    let sys = std::path::Path::new("/sys/class/net").join(name);
+    if sys.exists() {
+        if sys.join("wireless").exists() || sys.join("phy80211").exists() {
+            return InterfaceKind::Wifi;
+        }
+        if sys.join("tun_flags").exists() {
+            return InterfaceKind::Tunnel;
+        }
+        if sys.join("bridge").exists() {
+            return classify_by_name(name).filter(|kind| *kind == InterfaceKind::DockerBridge).unwrap_or(InterfaceKind::Bridge);
+        }
+        if let Some(kind) = classify_by_name(name) {
+            return kind;
+        }
+        // ARPHRD_ETHER is 1; WireGuard and other L3 tunnels report ARPHRD_NONE
+        let arp_type = std::fs::read_to_string(sys.join("type")).ok().and_then(|t| t.trim().parse::<u32>().ok());
+        return match arp_type {
+            Some(1) => InterfaceKind::Ethernet,
+            Some(65534) | Some(512) | Some(776) | Some(778) => InterfaceKind::Tunnel,
+            _ if flags & libc::IFF_POINTOPOINT as u32 != 0 => InterfaceKind::Tunnel,
+            _ => InterfaceKind::Other,
+        };
+    }
+    classify_by_name(name).unwrap_or(if flags & libc::IFF_POINTOPOINT as u32 != 0 {
+        InterfaceKind::Tunnel
+    } else {
+        InterfaceKind::Other
+    })
+}
+
+/// Well-known interface name prefixes (Linux, macOS and the BSDs)
+#[cfg_attr(not(unix), allow(dead_code))]
+fn classify_by_name(name: &str) -> Option<InterfaceKind> {
+    let prefixed = |prefixes: &[&str]| prefixes.iter().any(|prefix| name.starts_with(prefix));
+    if name == "docker0" || name.starts_with("br-") {
+        Some(InterfaceKind::DockerBridge)
+    } else if prefixed(&["veth"]) {
+        Some(InterfaceKind::Veth)
+    } else if prefixed(&["tun", "tap", "utun", "wg", "ppp", "ipsec", "tailscale", "zt"]) {
+        Some(InterfaceKind::Tunnel)
+    } else if prefixed(&["wlan", "wlp", "wlx", "awdl", "llw"]) {
+        Some(InterfaceKind::Wifi)
+    } else if prefixed(&["bridge", "virbr", "cni", "lxdbr"]) {
+        Some(InterfaceKind::Bridge)
+    } else if prefixed(&["eth", "enp", "eno", "ens", "enx", "en"]) {
+        Some(InterfaceKind::Ethernet)
+    } else {
+        None
+    }
+}
+
+/// Whether `ip` is inside `network/prefix`
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix.min(32) as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix.min(128) as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Local address → interface lookup built from one enumeration
+pub struct InterfaceMap {
+    by_address: HashMap<IpAddr, InterfaceInfo>,
+    /// Every address with its prefix length, longest prefix first
+    networks: Vec<(IpAddr, u8, InterfaceInfo)>,
+}
+
+impl InterfaceMap {
+    pub fn new(interfaces: Vec<NetworkInterface>) -> Self {
+        let mut by_address = HashMap::new();
+        let mut networks = Vec::new();
+        for interface in interfaces {
+            for (address, prefix) in interface.addresses.iter().zip(&interface.prefix_lengths) {
+                // The same address on two interfaces (anycast, misconfig): first wins
+                by_address.entry(*address).or_insert_with(|| interface.info.clone());
+                // A /0 would claim every address
+                if *prefix > 0 {
+                    networks.push((*address, *prefix, interface.info.clone()));
+                }
+            }
+        }
+        networks.sort_by_key(|(_, prefix, _)| std::cmp::Reverse(*prefix));
+        Self { by_address, networks }
+    }
+
+    /// Interface owning `address`; accepts `[v6]`, `%scope` suffixes and IPv4-mapped forms
+    ///
+    /// Addresses assigned implicitly, like 127.0.0.53 inside 127.0.0.0/8,
+    /// match the interface whose network contains them.
+    pub fn lookup(&self, address: &str) -> Option<&InterfaceInfo> {
+        let address = address.trim_start_matches('[').trim_end_matches(']');
+        let address = address.split('%').next().unwrap_or(address);
+        let ip = address.parse::<IpAddr>().ok()?.to_canonical();
+        if ip.is_unspecified() {
+            return None;
+        }
+        self.by_address.get(&ip).or_else(|| {
+            self.networks
+                .iter()
+                .find(|(network, prefix, _)| in_network(ip, *network, *prefix))
+                .map(|(_, _, info)| info)
+        })
+    }
+}
+
+/// Maps each socket's local address to the interface carrying it
+///
+/// Interfaces are enumerated afresh every snapshot, so VPNs coming up or
+/// DHCP renumbering show up on the next refresh.
+pub struct InterfaceEnricher;
+
+impl InterfaceEnricher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Enricher for InterfaceEnricher {
+    fn name(&self) -> &'static str {
+        "interfaces"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let interfaces = match list_interfaces() {
+            Ok(interfaces) => InterfaceMap::new(interfaces),
+            Err(e) => {
+                eprintln!("Backend Error: listing network interfaces failed: {}", e);
+                return;
+            }
+        };
+        for conn in connections.iter_mut() {
+            // Sockets in other namespaces use addresses from interfaces we cannot see
+            if matches!(conn.protocol, Protocol::Unix(_)) || conn.context.foreign_namespace {
+                continue;
+            }
+            conn.interface = interfaces.lookup(&conn.local_address).cloned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_names() {
+        let interface = |name: &str, kind: InterfaceKind, addresses: &[(&str, u8)]| NetworkInterface {
+            info: InterfaceInfo { name: name.to_string(), kind, flags: vec!["up".to_string()] },
+            addresses: addresses.iter().map(|(a, _)| a.parse().unwrap()).collect(),
+            prefix_lengths: addresses.iter().map(|(_, prefix)| *prefix).collect(),
+        };
+        let map = InterfaceMap::new(vec![
+            interface("lo", InterfaceKind::Loopback, &[("127.0.0.1", 8), ("::1", 128)]),
+            interface("wlp2s0", InterfaceKind::Wifi, &[("192.168.1.20", 24), ("fe80::1", 64)]),
+            interface("wg0", InterfaceKind::Tunnel, &[("10.8.0.2", 32)]),
+        ]);
+        assert_eq!(map.lookup("192.168.1.20").unwrap().name, "wlp2s0");
+        assert_eq!(map.lookup("::ffff:10.8.0.2").unwrap().kind, InterfaceKind::Tunnel);
+        assert_eq!(map.lookup("[::1]").unwrap().name, "lo");
+        assert_eq!(map.lookup("fe80::1%wlp2s0").unwrap().name, "wlp2s0");
+        assert_eq!(map.lookup("127.0.0.53").unwrap().name, "lo");
+        assert_eq!(map.lookup("192.168.1.99").unwrap().name, "wlp2s0");
+        assert!(map.lookup("10.8.0.3").is_none());
+        assert!(map.lookup("0.0.0.0").is_none());
+        assert!(map.lookup("*").is_none());
+
+        assert_eq!(classify_by_name("br-3f2a9c"), Some(InterfaceKind::DockerBridge));
+        assert_eq!(classify_by_name("utun3"), Some(InterfaceKind::Tunnel));
+        assert_eq!(classify_by_name("enp0s31f6"), Some(InterfaceKind::Ethernet));
+        assert_eq!(classify_by_name("dummy0"), None);
+    }
+}
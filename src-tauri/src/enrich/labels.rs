@@ -0,0 +1,355 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, ConnectionLabel};
+use crate::fs_util::{JsonStore, StoreError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// File name of the rule store inside the app data dir
+pub const LABELS_FILE: &str = "labels.json";
+
+/// Bumped whenever the on-disk layout changes incompatibly
+const LABELS_FILE_VERSION: u32 = 1;
+
+/// What a label rule matches against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "match", rename_all = "snake_case")]
+pub enum LabelMatch {
+    /// Either end of the connection uses this port
+    Port { port: u16 },
+    /// Either end uses a port in `start..=end`
+    PortRange { start: u16, end: u16 },
+    /// Process name, case-insensitive, with `*` and `?` wildcards
+    Process { pattern: String },
+    /// Either end has this address, or falls in this CIDR block
+    Address { address: String },
+}
+
+/// A user rule: connections matching `matcher` get `label`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelRule {
+    /// Assigned on creation; ignored when creating a rule
+    #[serde(default)]
+    pub id: String,
+    pub label: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(flatten)]
+    pub matcher: LabelMatch,
+}
+
+impl LabelRule {
+    pub fn validate(&self) -> Result<(), LabelError> {
+        if self.label.trim().is_empty() {
+            return Err(LabelError::Invalid("label must not be empty".to_string()));
+        }
+        if let Some(color) = &self.color {
+            if !is_hex_color(color) {
+                return Err(LabelError::Invalid(format!("'{}' is not a #rgb or #rrggbb colour", color)));
+            }
+        }
+        match &self.matcher {
+            LabelMatch::Port { port } if *port == 0 => Err(LabelError::Invalid("port must be 1-65535".to_string())),
+            LabelMatch::PortRange { start, end } if *start == 0 || start > end => {
+                Err(LabelError::Invalid(format!("invalid port range {}-{}", start, end)))
+            }
+            LabelMatch::Process { pattern } if pattern.trim().is_empty() => {
+                Err(LabelError::Invalid("process pattern must not be empty".to_string()))
+            }
+            LabelMatch::Address { address } if parse_cidr(address).is_none() => {
+                Err(LabelError::Invalid(format!("'{}' is not an IP address or CIDR block", address)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn matches(&self, conn: &ConnectionInfo) -> bool {
+        match &self.matcher {
+            LabelMatch::Port { port } => ports(conn).any(|p| p == *port),
+            LabelMatch::PortRange { start, end } => ports(conn).any(|p| (*start..=*end).contains(&p)),
+            LabelMatch::Process { pattern } => glob_match(&pattern.to_lowercase(), &conn.process_name.to_lowercase()),
+            LabelMatch::Address { address } => {
+                let Some((network, prefix)) = parse_cidr(address) else {
+                    return false;
+                };
+                [&conn.local_address, &conn.remote_address]
+                    .iter()
+                    .filter_map(|address| address.parse::<IpAddr>().ok())
+                    .any(|ip| cidr_contains(network, prefix, ip))
+            }
+        }
+    }
+}
+
+fn ports(conn: &ConnectionInfo) -> impl Iterator<Item = u16> {
+    [conn.local_port, conn.remote_port].into_iter().filter(|port| *port != 0)
+}
+
+/// Errors from validating or persisting label rules
+#[derive(Debug, Clone)]
+pub enum LabelError {
+    /// Rule failed validation
+    Invalid(String),
+    /// No rule with this id
+    NotFound(String),
+    /// Reading or writing the rule file failed
+    Io(String),
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelError::Invalid(msg) => write!(f, "Invalid label rule: {}", msg),
+            LabelError::NotFound(id) => write!(f, "No label rule with id {}", id),
+            LabelError::Io(msg) => write!(f, "Label store I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+impl From<StoreError> for LabelError {
+    fn from(e: StoreError) -> Self {
+        LabelError::Io(e.0)
+    }
+}
+
+/// Label rules shared between the enricher and the Tauri commands
+///
+/// Rules live in memory until `load` points the store at a file; from then on
+/// every change is written back immediately.
+pub struct LabelStore {
+    rules: JsonStore<LabelRule>,
+}
+
+impl LabelStore {
+    pub fn new() -> Self {
+        Self {
+            rules: JsonStore::new("rules", LABELS_FILE_VERSION),
+        }
+    }
+
+    /// Read rules from `path` and persist to it from now on; a missing file means no rules
+    ///
+    /// An unreadable file is copied to `<path>.bak` first so the next save
+    /// does not silently destroy the user's rules; a file from a newer
+    /// version is never saved over.
+    pub fn load(&self, path: PathBuf) -> Result<usize, LabelError> {
+        // Drop rules that no longer validate instead of refusing the whole file
+        Ok(self.rules.load(path, |rule: &LabelRule| match rule.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                println!("Labels: skipping rule '{}': {}", rule.label, e);
+                false
+            }
+        })?)
+    }
+
+    pub fn rules(&self) -> Vec<LabelRule> {
+        self.rules.items()
+    }
+
+    /// Add a rule with a fresh id
+    pub fn create(&self, mut rule: LabelRule) -> Result<LabelRule, LabelError> {
+        rule.validate()?;
+        rule.id = uuid::Uuid::new_v4().to_string();
+
+        self.rules.update(|rules| {
+            rules.push(rule.clone());
+            Ok(rule)
+        })
+    }
+
+    /// Replace the rule with the same id, keeping its position
+    pub fn update(&self, rule: LabelRule) -> Result<LabelRule, LabelError> {
+        rule.validate()?;
+
+        self.rules.update(|rules| {
+            let existing = rules
+                .iter_mut()
+                .find(|existing| existing.id == rule.id)
+                .ok_or_else(|| LabelError::NotFound(rule.id.clone()))?;
+            *existing = rule.clone();
+            Ok(rule)
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), LabelError> {
+        self.rules.update(|rules| {
+            let before = rules.len();
+            rules.retain(|rule| rule.id != id);
+            if rules.len() == before {
+                return Err(LabelError::NotFound(id.to_string()));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Attaches `labels` from the user's rules
+pub struct LabelEnricher {
+    store: Arc<LabelStore>,
+}
+
+impl LabelEnricher {
+    pub fn new(store: Arc<LabelStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl Enricher for LabelEnricher {
+    fn name(&self) -> &'static str {
+        "labels"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let rules = self.store.rules();
+        if rules.is_empty() {
+            return;
+        }
+        for conn in connections.iter_mut() {
+            let mut labels: Vec<ConnectionLabel> = Vec::new();
+            for rule in rules.iter().filter(|rule| rule.matches(conn)) {
+                // The first rule to use a label decides its colour
+                if !labels.iter().any(|existing| existing.label == rule.label) {
+                    labels.push(ConnectionLabel {
+                        label: rule.label.clone(),
+                        color: rule.color.clone(),
+                    });
+                }
+            }
+            conn.labels = labels;
+        }
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// `10.0.0.0/8`, `fd00::/8`, or a bare address (a /32 or /128)
+pub fn parse_cidr(value: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix) = match value.trim().split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u8>().ok()?)),
+        None => (value.trim(), None),
+    };
+    let ip: IpAddr = address.parse().ok()?;
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    (prefix <= max).then_some((ip, prefix))
+}
+
+/// Whether `ip` falls inside `network/prefix`; address families never mix
+pub fn cidr_contains(network: IpAddr, prefix: u8, ip: IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Shell-style match supporting `*` (any run) and `?` (one character)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+    use std::fs;
+
+    fn rule(label: &str, matcher: LabelMatch) -> LabelRule {
+        LabelRule {
+            id: String::new(),
+            label: label.to_string(),
+            color: Some("#22c55e".to_string()),
+            matcher,
+        }
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let conn = ConnectionInfo::new(
+            Protocol::TCP,
+            "10.1.2.3".to_string(),
+            9229,
+            "192.168.1.20".to_string(),
+            51000,
+            ConnectionState::Established,
+            42,
+            "Node".to_string(),
+        );
+
+        assert!(rule("debugger", LabelMatch::Port { port: 9229 }).matches(&conn));
+        assert!(!rule("auth", LabelMatch::Port { port: 8081 }).matches(&conn));
+        assert!(rule("ephemeral", LabelMatch::PortRange { start: 49152, end: 65535 }).matches(&conn));
+        assert!(rule("node", LabelMatch::Process { pattern: "no?e*".to_string() }).matches(&conn));
+        assert!(!rule("python", LabelMatch::Process { pattern: "python*".to_string() }).matches(&conn));
+        assert!(rule("lan", LabelMatch::Address { address: "192.168.0.0/16".to_string() }).matches(&conn));
+        assert!(rule("host", LabelMatch::Address { address: "10.1.2.3".to_string() }).matches(&conn));
+        assert!(!rule("v6", LabelMatch::Address { address: "fd00::/8".to_string() }).matches(&conn));
+    }
+
+    #[test]
+    fn test_validation_and_file_format() {
+        assert!(rule("", LabelMatch::Port { port: 80 }).validate().is_err());
+        assert!(rule("x", LabelMatch::PortRange { start: 90, end: 80 }).validate().is_err());
+        assert!(rule("x", LabelMatch::Address { address: "10.0.0.0/33".to_string() }).validate().is_err());
+        let mut bad_color = rule("x", LabelMatch::Port { port: 80 });
+        bad_color.color = Some("green".to_string());
+        assert!(bad_color.validate().is_err());
+
+        let parsed: LabelRule =
+            serde_json::from_str(r##"{"label":"auth","color":"#f00","match":"port","port":8081}"##).unwrap();
+        assert_eq!(parsed.matcher, LabelMatch::Port { port: 8081 });
+        assert!(parsed.validate().is_ok());
+    }
+
+    #[test]
+    fn test_newer_file_is_left_alone() {
+        let path = std::env::temp_dir().join(format!("portviewer-labels-{}.json", std::process::id()));
+        let newer = r#"{"version":2,"rules":[{"label":"future","match":"tag","tag":"x"}]}"#;
+        fs::write(&path, newer).unwrap();
+
+        let store = LabelStore::new();
+        assert!(store.load(path.clone()).is_err());
+        assert!(matches!(store.create(rule("debugger", LabelMatch::Port { port: 9229 })), Err(LabelError::Io(_))));
+        assert!(store.rules().is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(path.with_extension("json.bak")).unwrap();
+    }
+}
@@ -0,0 +1,350 @@
+use std::net::IpAddr;
+
+/// Marks the start of the metadata section, near the end of the file
+const METADATA_MARKER: &[u8] = b"\xAB\xCD\xEFMaxMind.com";
+/// The metadata section is at most this far from the end
+const METADATA_MAX_SIZE: usize = 128 * 1024;
+/// Zero bytes between the search tree and the data section
+const DATA_SECTION_SEPARATOR: usize = 16;
+/// Guards against maliciously nested maps and arrays
+const MAX_DEPTH: usize = 32;
+
+/// A decoded data section value; only what the GeoIP enricher reads is kept precise
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Unsigned(u64),
+    Signed(i64),
+    Double(f64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Entry of a map by key
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Unsigned(n) => Some(*n),
+            Value::Signed(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Read-only MaxMind DB (`.mmdb`) file, held in memory
+///
+/// Implements the binary search tree and data section of the MaxMind DB 2.0
+/// format, enough to look up GeoLite2, DB-IP and ipinfo databases without a
+/// dependency.
+pub struct MaxMindDb {
+    data: Vec<u8>,
+    node_count: usize,
+    record_size: usize,
+    ip_version: u64,
+    /// `database_type` from the metadata, e.g. `GeoLite2-ASN`
+    pub database_type: String,
+    tree_size: usize,
+    /// Node reached after 96 zero bits, where IPv4 lookups start in an IPv6 tree
+    ipv4_start: usize,
+}
+
+impl MaxMindDb {
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, String> {
+        let search_from = data.len().saturating_sub(METADATA_MAX_SIZE);
+        let marker = data[search_from..]
+            .windows(METADATA_MARKER.len())
+            .rposition(|window| window == METADATA_MARKER)
+            .ok_or("not a MaxMind DB file (no metadata marker)")?;
+        let metadata_start = search_from + marker + METADATA_MARKER.len();
+        let (metadata, _) = Decoder { data: &data, base: metadata_start }.decode(metadata_start, 0)?;
+
+        let field = |key: &str| metadata.get(key).and_then(Value::as_u64).ok_or(format!("metadata lacks '{}'", key));
+        let node_count = field("node_count")? as usize;
+        let record_size = field("record_size")? as usize;
+        let ip_version = field("ip_version")?;
+        if !matches!(record_size, 24 | 28 | 32) {
+            return Err(format!("unsupported record size {}", record_size));
+        }
+        let tree_size = node_count
+            .checked_mul(record_size / 4)
+            .filter(|size| size.saturating_add(DATA_SECTION_SEPARATOR) <= metadata_start)
+            .ok_or("search tree runs past the end of the file")?;
+
+        let mut db = Self {
+            database_type: metadata.get("database_type").and_then(Value::as_str).unwrap_or("unknown").to_string(),
+            data,
+            node_count,
+            record_size,
+            ip_version,
+            tree_size,
+            ipv4_start: 0,
+        };
+        if ip_version == 6 {
+            let mut node = 0;
+            for _ in 0..96 {
+                if node >= node_count {
+                    break;
+                }
+                node = db.record(node, 0)?;
+            }
+            db.ipv4_start = node;
+        }
+        Ok(db)
+    }
+
+    /// The data record for the network containing `ip`, if any
+    pub fn lookup(&self, ip: IpAddr) -> Result<Option<Value>, String> {
+        let (bits, bit_count, mut node) = match ip.to_canonical() {
+            IpAddr::V4(v4) if self.ip_version == 6 => (u32::from(v4) as u128, 32, self.ipv4_start),
+            IpAddr::V4(v4) => (u32::from(v4) as u128, 32, 0),
+            // An IPv4-only database has no IPv6 networks
+            IpAddr::V6(_) if self.ip_version == 4 => return Ok(None),
+            IpAddr::V6(v6) => (u128::from(v6), 128, 0),
+        };
+
+        for i in (0..bit_count).rev() {
+            if node >= self.node_count {
+                break;
+            }
+            node = self.record(node, ((bits >> i) & 1) as usize)?;
+        }
+        // Equal to the node count means "no data"; below it, the bits ran out inside the tree
+        if node <= self.node_count {
+            return Ok(None);
+        }
+
+        // Records between the node count and the data section point into the separator
+        let offset = node
+            .checked_sub(self.node_count + DATA_SECTION_SEPARATOR)
+            .ok_or("search tree record points into the data section separator")?;
+        let base = self.tree_size + DATA_SECTION_SEPARATOR;
+        let decoder = Decoder { data: &self.data, base };
+        decoder.decode(base + offset, 0).map(|(value, _)| Some(value))
+    }
+
+    /// Left (0) or right (1) record of `node`
+    fn record(&self, node: usize, side: usize) -> Result<usize, String> {
+        let bytes_per_node = self.record_size / 4;
+        let start = node * bytes_per_node;
+        let b = self
+            .data
+            .get(start..start + bytes_per_node)
+            .ok_or("search tree node out of bounds")?;
+        let be = |bytes: &[u8]| bytes.iter().fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+        Ok(match (self.record_size, side) {
+            (24, 0) => be(&b[0..3]),
+            (24, _) => be(&b[3..6]),
+            (28, 0) => ((b[3] as usize & 0xF0) << 20) | be(&b[0..3]),
+            (28, _) => ((b[3] as usize & 0x0F) << 24) | be(&b[4..7]),
+            (_, 0) => be(&b[0..4]),
+            (_, _) => be(&b[4..8]),
+        })
+    }
+}
+
+/// Decodes values of the data (or metadata) section; pointers are relative to `base`
+struct Decoder<'a> {
+    data: &'a [u8],
+    base: usize,
+}
+
+impl Decoder<'_> {
+    fn byte(&self, at: usize) -> Result<u8, String> {
+        self.data.get(at).copied().ok_or_else(|| "data section truncated".to_string())
+    }
+
+    fn slice(&self, at: usize, len: usize) -> Result<&[u8], String> {
+        self.data.get(at..at + len).ok_or_else(|| "data section truncated".to_string())
+    }
+
+    fn uint(&self, at: usize, len: usize) -> Result<u64, String> {
+        if len > 16 {
+            return Err("integer too large".to_string());
+        }
+        // uint128 values are only kept when they fit
+        Ok(self.slice(at, len)?.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128) as u64)
+    }
+
+    /// Decode the value at `at`, returning it and the position after it
+    fn decode(&self, at: usize, depth: usize) -> Result<(Value, usize), String> {
+        if depth > MAX_DEPTH {
+            return Err("data nested too deeply".to_string());
+        }
+        let control = self.byte(at)?;
+        let mut pos = at + 1;
+        let mut kind = control >> 5;
+
+        if kind == 1 {
+            // Pointer: the value lives elsewhere; decoding resumes after the pointer
+            let size = ((control >> 3) & 0x3) as usize;
+            let low = (control & 0x7) as usize;
+            let bytes = self.slice(pos, size + 1)?;
+            let be = bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+            let target = match size {
+                0 => (low << 8) | be,
+                1 => ((low << 16) | be) + 2048,
+                2 => ((low << 24) | be) + 526_336,
+                _ => be,
+            };
+            let (value, _) = self.decode(self.base + target, depth + 1)?;
+            return Ok((value, pos + size + 1));
+        }
+        if kind == 0 {
+            kind = self.byte(pos)?.checked_add(7).ok_or("unsupported extended data type")?;
+            pos += 1;
+        }
+
+        let mut size = (control & 0x1f) as usize;
+        match size {
+            29 => {
+                size = 29 + self.byte(pos)? as usize;
+                pos += 1;
+            }
+            30 => {
+                size = 285 + self.uint(pos, 2)? as usize;
+                pos += 2;
+            }
+            31 => {
+                size = 65_821 + self.uint(pos, 3)? as usize;
+                pos += 3;
+            }
+            _ => {}
+        }
+
+        match kind {
+            2 => {
+                let text = String::from_utf8_lossy(self.slice(pos, size)?).into_owned();
+                Ok((Value::String(text), pos + size))
+            }
+            3 => {
+                let bytes: [u8; 8] = self.slice(pos, 8)?.try_into().map_err(|_| "bad double")?;
+                Ok((Value::Double(f64::from_be_bytes(bytes)), pos + 8))
+            }
+            4 => Ok((Value::Bytes(self.slice(pos, size)?.to_vec()), pos + size)),
+            5 | 6 | 9 | 10 => Ok((Value::Unsigned(self.uint(pos, size)?), pos + size)),
+            7 => {
+                let mut entries = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (key, next) = self.decode(pos, depth + 1)?;
+                    let (value, next) = self.decode(next, depth + 1)?;
+                    let Value::String(key) = key else {
+                        return Err("map key is not a string".to_string());
+                    };
+                    entries.push((key, value));
+                    pos = next;
+                }
+                Ok((Value::Map(entries), pos))
+            }
+            8 => {
+                // Stored in up to four bytes; sign-extend from the full width
+                let raw = self.uint(pos, size)? as u32;
+                Ok((Value::Signed(raw as i32 as i64), pos + size))
+            }
+            11 => {
+                let mut items = Vec::with_capacity(size.min(64));
+                for _ in 0..size {
+                    let (value, next) = self.decode(pos, depth + 1)?;
+                    items.push(value);
+                    pos = next;
+                }
+                Ok((Value::Array(items), pos))
+            }
+            14 => Ok((Value::Bool(size != 0), pos)),
+            15 => {
+                let bytes: [u8; 4] = self.slice(pos, 4)?.try_into().map_err(|_| "bad float")?;
+                Ok((Value::Double(f32::from_be_bytes(bytes) as f64), pos + 4))
+            }
+            other => Err(format!("unsupported data type {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a tiny IPv4 database: 10.0.0.0/8 maps to {"autonomous_system_number": 64512}
+    pub(crate) fn tiny_database() -> Vec<u8> {
+        // Eight nodes walk the bits of 10 (00001010); every other branch is "not found"
+        let node_count = 8usize;
+        let mut tree = Vec::new();
+        let bits = [0, 0, 0, 0, 1, 0, 1, 0];
+        for (node, bit) in bits.iter().enumerate() {
+            let next = if node + 1 < node_count { node + 1 } else { node_count + DATA_SECTION_SEPARATOR };
+            let (left, right) = if *bit == 0 { (next, node_count) } else { (node_count, next) };
+            for record in [left, right] {
+                tree.extend_from_slice(&(record as u32).to_be_bytes()[1..]);
+            }
+        }
+
+        let mut data = tree;
+        data.extend_from_slice(&[0; DATA_SECTION_SEPARATOR]);
+        // map(1) { "autonomous_system_number": uint32 64512 }
+        data.push(0xE1);
+        let key = b"autonomous_system_number";
+        data.push(0x40 | key.len() as u8);
+        data.extend_from_slice(key);
+        data.extend_from_slice(&[0xC2, 0xFC, 0x00]);
+
+        data.extend_from_slice(METADATA_MARKER);
+        let mut metadata = vec![0xE4];
+        for (key, value) in [("node_count", 8u8), ("record_size", 24), ("ip_version", 4)] {
+            metadata.push(0x40 | key.len() as u8);
+            metadata.extend_from_slice(key.as_bytes());
+            metadata.extend_from_slice(&[0xC1, value]);
+        }
+        metadata.push(0x40 | 13);
+        metadata.extend_from_slice(b"database_type");
+        metadata.push(0x40 | 4);
+        metadata.extend_from_slice(b"test");
+        data.extend_from_slice(&metadata);
+        data
+    }
+
+    #[test]
+    fn test_lookup_in_handmade_database() {
+        let db = MaxMindDb::from_bytes(tiny_database()).unwrap();
+        assert_eq!(db.database_type, "test");
+
+        let hit = db.lookup("10.1.2.3".parse().unwrap()).unwrap().unwrap();
+        assert_eq!(hit.get("autonomous_system_number").and_then(Value::as_u64), Some(64512));
+        assert!(db.lookup("11.1.2.3".parse().unwrap()).unwrap().is_none());
+        assert!(db.lookup("2001:db8::1".parse().unwrap()).unwrap().is_none());
+        assert!(MaxMindDb::from_bytes(b"not a database".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_malformed_databases_are_errors() {
+        // node_count of u64::MAX overflows the tree size
+        let mut data = tiny_database();
+        let at = data.windows(10).rposition(|w| w == b"node_count").unwrap() + 10;
+        data.splice(at..at + 2, [0x08, 0x02, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(MaxMindDb::from_bytes(data).is_err());
+
+        // The last node points one past the node count, inside the separator
+        let mut data = tiny_database();
+        data[7 * 6..7 * 6 + 3].copy_from_slice(&[0, 0, 9]);
+        let db = MaxMindDb::from_bytes(data).unwrap();
+        assert!(db.lookup("10.1.2.3".parse().unwrap()).is_err());
+
+        // Extended type 255 does not fit in the type byte
+        let decoder = Decoder { data: &[0x00, 0xFF], base: 0 };
+        assert!(decoder.decode(0, 0).is_err());
+    }
+}
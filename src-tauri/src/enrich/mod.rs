@@ -0,0 +1,104 @@
+use crate::models::ConnectionInfo;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub mod dns;
+pub mod findings;
+pub mod geoip;
+pub mod interfaces;
+pub mod labels;
+pub mod mmdb;
+pub mod services;
+
+#[cfg(target_os = "linux")]
+pub mod container;
+#[cfg(target_os = "linux")]
+pub mod forwarder;
+#[cfg(target_os = "linux")]
+pub mod systemd;
+
+/// A post-processing step that annotates collected connections
+///
+/// Enrichers run after the backend on the blocking pool, so they may read
+/// files or make system calls, but they must never fail the snapshot:
+/// missing information simply stays `None`.
+pub trait Enricher: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]);
+}
+
+/// Enrichers slower than this are reported so regressions are noticed
+const SLOW_ENRICHER_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Ordered list of enrichers applied to every snapshot
+pub struct EnrichmentPipeline {
+    enrichers: Vec<Box<dyn Enricher>>,
+    reverse_dns: Arc<dns::ReverseDnsResolver>,
+    geoip: Arc<geoip::GeoIpStore>,
+    labels: Arc<labels::LabelStore>,
+    service_overrides: Arc<services::ServiceOverrides>,
+}
+
+impl EnrichmentPipeline {
+    /// Pipeline with every enricher supported on this platform
+    pub fn new() -> Self {
+        let reverse_dns = Arc::new(dns::ReverseDnsResolver::new());
+        let geoip = Arc::new(geoip::GeoIpStore::new());
+        let labels = Arc::new(labels::LabelStore::new());
+        let service_overrides = Arc::new(services::ServiceOverrides::new());
+        let enrichers: Vec<Box<dyn Enricher>> = vec![
+            Box::new(services::ServiceEnricher::new(service_overrides.clone())),
+            #[cfg(target_os = "linux")]
+            Box::new(container::ContainerEnricher::new()),
+            #[cfg(target_os = "linux")]
+            Box::new(forwarder::ForwarderEnricher::new()),
+            // Reads the cgroup path filled in by the container enricher
+            #[cfg(target_os = "linux")]
+            Box::new(systemd::SystemdEnricher::new()),
+            Box::new(interfaces::InterfaceEnricher::new()),
+            // Reads the executable path filled in by the container enricher
+            Box::new(findings::FindingsEnricher::new()),
+            Box::new(dns::ReverseDnsEnricher::new(reverse_dns.clone())),
+            Box::new(geoip::GeoIpEnricher::new(geoip.clone())),
+            Box::new(labels::LabelEnricher::new(labels.clone())),
+        ];
+        Self {
+            enrichers,
+            reverse_dns,
+            geoip,
+            labels,
+            service_overrides,
+        }
+    }
+
+    /// Shared reverse-DNS cache, switched off until the user enables it
+    pub fn reverse_dns(&self) -> &dns::ReverseDnsResolver {
+        &self.reverse_dns
+    }
+
+    /// Local ASN/GeoIP databases, none until configured in the settings
+    pub fn geoip(&self) -> &geoip::GeoIpStore {
+        &self.geoip
+    }
+
+    /// User label rules, empty until loaded from the app data dir
+    pub fn labels(&self) -> &labels::LabelStore {
+        &self.labels
+    }
+
+    /// User service names, none until a config dir is known
+    pub fn service_overrides(&self) -> &services::ServiceOverrides {
+        &self.service_overrides
+    }
+
+    pub fn run(&self, connections: &mut [ConnectionInfo]) {
+        for enricher in &self.enrichers {
+            let started = Instant::now();
+            enricher.enrich(connections);
+            if started.elapsed() > SLOW_ENRICHER_THRESHOLD {
+                println!("Enricher '{}' took {}ms", enricher.name(), started.elapsed().as_millis());
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, Protocol};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// IANA assignments shipped with the app, for systems without a services file
+const BUNDLED_SERVICES: &str = include_str!("iana_services.txt");
+
+/// Bundled `port/protocol name` overrides for common dev and infra ports
+const BUNDLED_OVERRIDES: &str = include_str!("port_overrides.txt");
+
+/// File name of the user's overrides inside the app config dir
+pub const SERVICE_OVERRIDES_FILE: &str = "service-overrides.txt";
+
+/// Port → service name table merged from the bundled IANA subset, the
+/// system services file and the bundled overrides (later sources win)
+pub struct ServiceTable {
+    names: HashMap<(String, u16), String>,
+}
+
+impl ServiceTable {
+    pub fn load() -> Self {
+        Self::with_system_services(&fs::read_to_string(system_services_path()).unwrap_or_default())
+    }
+
+    fn with_system_services(system: &str) -> Self {
+        let mut names = HashMap::new();
+        for content in [BUNDLED_SERVICES, system] {
+            for (name, port, protocol) in parse_services(content) {
+                names.insert((protocol, port), name);
+            }
+        }
+        for (name, port, protocol) in parse_overrides(BUNDLED_OVERRIDES) {
+            names.insert((protocol, port), name);
+        }
+        Self { names }
+    }
+
+    /// Service name for `port` over `protocol` (`tcp`, `udp`, `sctp`)
+    pub fn lookup(&self, protocol: &str, port: u16) -> Option<&str> {
+        self.names.get(&(protocol.to_string(), port)).map(String::as_str)
+    }
+}
+
+/// Parsed user overrides plus the mtime they were read at
+#[derive(Default)]
+struct OverridesFile {
+    modified: Option<SystemTime>,
+    names: HashMap<(String, u16), String>,
+}
+
+/// The user's own service names, from `service-overrides.txt` in the config dir
+///
+/// Same `port/protocol name` format as the bundled overrides, and these win
+/// over every other source. The file is re-read whenever it changes, so
+/// edits show up on the next refresh; a missing file means no overrides.
+pub struct ServiceOverrides {
+    path: Mutex<Option<PathBuf>>,
+    file: Mutex<OverridesFile>,
+}
+
+impl ServiceOverrides {
+    pub fn new() -> Self {
+        Self {
+            path: Mutex::new(None),
+            file: Mutex::new(OverridesFile::default()),
+        }
+    }
+
+    /// Read overrides from `path` from now on
+    pub fn set_path(&self, path: PathBuf) {
+        *self.path.lock().unwrap() = Some(path);
+        *self.file.lock().unwrap() = OverridesFile::default();
+    }
+
+    pub fn path(&self) -> Option<PathBuf> {
+        self.path.lock().unwrap().clone()
+    }
+
+    /// Current overrides, re-reading the file if its mtime changed
+    fn names(&self) -> HashMap<(String, u16), String> {
+        let Some(path) = self.path() else {
+            return HashMap::new();
+        };
+        let mut file = self.file.lock().unwrap();
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if modified != file.modified {
+            let content = fs::read_to_string(&path).unwrap_or_default();
+            *file = OverridesFile {
+                modified,
+                names: parse_overrides(&content)
+                    .into_iter()
+                    .map(|(name, port, protocol)| ((protocol, port), name))
+                    .collect(),
+            };
+        }
+        file.names.clone()
+    }
+}
+
+fn system_services_path() -> String {
+    if cfg!(target_os = "windows") {
+        let root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+        format!("{}\\System32\\drivers\\etc\\services", root)
+    } else {
+        "/etc/services".to_string()
+    }
+}
+
+/// Parse services(5) lines: `name port/protocol [aliases...] [# comment]`
+pub fn parse_services(content: &str) -> Vec<(String, u16, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next()?;
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let (port, protocol) = fields.next()?.split_once('/')?;
+            Some((name.to_string(), port.parse().ok()?, protocol.to_lowercase()))
+        })
+        .collect()
+}
+
+/// Override lines: `port/protocol name [# comment]`; the name may contain spaces
+pub fn parse_overrides(content: &str) -> Vec<(String, u16, String)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next()?.trim();
+            let (port, name) = line.split_once(char::is_whitespace)?;
+            let (port, protocol) = port.split_once('/')?;
+            let name = name.trim();
+            if name.is_empty() {
+                return None;
+            }
+            Some((name.to_string(), port.parse().ok()?, protocol.to_lowercase()))
+        })
+        .collect()
+}
+
+/// Fills `service_name` from the port the row's server side uses
+pub struct ServiceEnricher {
+    table: OnceLock<ServiceTable>,
+    overrides: Arc<ServiceOverrides>,
+}
+
+impl ServiceEnricher {
+    pub fn new(overrides: Arc<ServiceOverrides>) -> Self {
+        Self {
+            table: OnceLock::new(),
+            overrides,
+        }
+    }
+}
+
+impl Enricher for ServiceEnricher {
+    fn name(&self) -> &'static str {
+        "services"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let table = self.table.get_or_init(ServiceTable::load);
+        let overrides = self.overrides.names();
+
+        for conn in connections.iter_mut() {
+            let protocol = match conn.protocol {
+                Protocol::TCP => "tcp",
+                Protocol::UDP => "udp",
+                Protocol::SCTP => "sctp",
+                _ => continue,
+            };
+            // Listeners are the service; otherwise the lower port is almost
+            // always the server end and the other one is ephemeral
+            let candidates = if conn.state.is_listening() || conn.remote_port == 0 {
+                vec![conn.local_port]
+            } else if conn.local_port <= conn.remote_port {
+                vec![conn.local_port, conn.remote_port]
+            } else {
+                vec![conn.remote_port, conn.local_port]
+            };
+
+            conn.service_name = candidates
+                .into_iter()
+                .find_map(|port| {
+                    overrides
+                        .get(&(protocol.to_string(), port))
+                        .map(String::as_str)
+                        .or_else(|| table.lookup(protocol, port))
+                })
+                .map(str::to_string);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    #[test]
+    fn test_parse_services() {
+        let entries = parse_services("# comment\npostgresql\t5432/tcp\tpostgres # PostgreSQL\n\nbogus line\nntp 123/UDP\n");
+        assert_eq!(
+            entries,
+            vec![
+                ("postgresql".to_string(), 5432, "tcp".to_string()),
+                ("ntp".to_string(), 123, "udp".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_prefers_overrides() {
+        let table = ServiceTable::with_system_services("");
+        assert_eq!(table.lookup("tcp", 5432), Some("postgresql"));
+        assert_eq!(table.lookup("udp", 5432), None);
+        assert_eq!(table.lookup("tcp", 3000), Some("frontend dev server"));
+        assert_eq!(table.lookup("tcp", 9090), Some("prometheus"));
+        // Overrides are per transport
+        assert_eq!(table.lookup("udp", 51820), Some("wireguard"));
+        assert_ne!(table.lookup("udp", 3000), Some("frontend dev server"));
+
+        assert_eq!(
+            parse_overrides("# mine\n8080/tcp  my api  # staging\n8080 nope\n9000/udp\n"),
+            vec![("my api".to_string(), 8080, "tcp".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_user_overrides_win() {
+        let path = std::env::temp_dir().join(format!("portviewer-services-{}.txt", std::process::id()));
+        fs::write(&path, "5432/tcp\tour postgres\n").unwrap();
+        let overrides = Arc::new(ServiceOverrides::new());
+        overrides.set_path(path.clone());
+
+        let mut connections = vec![ConnectionInfo::new(
+            Protocol::TCP,
+            "0.0.0.0".to_string(),
+            5432,
+            "*".to_string(),
+            0,
+            ConnectionState::Listening,
+            1,
+            "postgres".to_string(),
+        )];
+        ServiceEnricher::new(overrides).enrich(&mut connections);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(connections[0].service_name.as_deref(), Some("our postgres"));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::enrich::Enricher;
+use crate::models::{ConnectionInfo, Protocol, UnixSocketType};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// System unit search path, highest priority first (systemd.unit(5))
+const UNIT_DIRS: [&str; 4] = [
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// User unit search path below the per-user config and runtime dirs
+const USER_UNIT_DIRS: [&str; 3] = ["/etc/systemd/user", "/usr/lib/systemd/user", "/lib/systemd/user"];
+
+/// Socket type a `.socket` unit listens with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListenKind {
+    Stream,
+    Datagram,
+    SequentialPacket,
+}
+
+/// What a `Listen*=` line binds to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListenEndpoint {
+    /// Port, optionally restricted to one address
+    Port { address: Option<String>, port: u16 },
+    /// Unix socket path, or `@name` for an abstract socket
+    Path(String),
+}
+
+/// One `Listen*=` entry of a `.socket` unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct SocketListen {
+    pub unit: String,
+    pub kind: ListenKind,
+    pub endpoint: ListenEndpoint,
+}
+
+/// Attributes each connection to the systemd unit owning it
+///
+/// Regular processes get the deepest `*.service` (or `*.scope`) from their
+/// cgroup path, which the container enricher has already read. Listeners held
+/// by pid 1 are socket-activated, so they are matched against the `Listen*=`
+/// lines of the system and user `.socket` unit files on disk instead, as are
+/// listeners whose owner is unknown (pid 0 without root) or has no unit in its
+/// cgroup, such as a user manager. `systemctl` is never run.
+pub struct SystemdEnricher;
+
+impl SystemdEnricher {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Enricher for SystemdEnricher {
+    fn name(&self) -> &'static str {
+        "systemd"
+    }
+
+    fn enrich(&self, connections: &mut [ConnectionInfo]) {
+        let mut socket_units: Option<Vec<SocketListen>> = None;
+
+        for conn in connections.iter_mut() {
+            let cgroup_unit = conn.context.cgroup.as_deref().and_then(unit_from_cgroup);
+            if conn.state.is_listening() && (conn.pid == 1 || cgroup_unit.is_none()) {
+                let listens = socket_units.get_or_insert_with(load_socket_units);
+                if let Some(unit) = match_socket_unit(listens, conn) {
+                    conn.context.systemd_unit = Some(unit.to_string());
+                    continue;
+                }
+            }
+            conn.context.systemd_unit = cgroup_unit;
+        }
+    }
+}
+
+/// Deepest `.service` in a cgroup path, falling back to the deepest `.scope`
+///
+/// `/user.slice/user-1000.slice/user@1000.service/app.slice/foo.service` → `foo.service`
+pub fn unit_from_cgroup(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path.split('/').collect();
+    [".service", ".scope"].iter().find_map(|suffix| {
+        segments
+            .iter()
+            .rev()
+            .find(|segment| segment.ends_with(suffix))
+            .map(|segment| segment.to_string())
+    })
+}
+
+/// Parse every system and user `.socket` unit, system units first
+///
+/// Within each search path earlier directories mask later ones. Units are
+/// sorted by name so the same listener always maps to the same unit.
+pub fn load_socket_units() -> Vec<SocketListen> {
+    let mut listens = read_socket_units(UNIT_DIRS.iter().map(PathBuf::from));
+    listens.extend(read_socket_units(user_unit_dirs()));
+    listens
+}
+
+/// `$XDG_CONFIG_HOME/systemd/user`, `$XDG_RUNTIME_DIR/systemd/user`, then the shared dirs
+fn user_unit_dirs() -> Vec<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    let runtime = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    config
+        .into_iter()
+        .chain(runtime)
+        .map(|dir| dir.join("systemd/user"))
+        .chain(USER_UNIT_DIRS.iter().map(PathBuf::from))
+        .collect()
+}
+
+fn read_socket_units(dirs: impl IntoIterator<Item = PathBuf>) -> Vec<SocketListen> {
+    let mut units: BTreeMap<String, String> = BTreeMap::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !name.ends_with(".socket") || units.contains_key(&name) {
+                continue;
+            }
+            // Masked units are symlinks to /dev/null and read as empty
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                units.insert(name, content);
+            }
+        }
+    }
+
+    units
+        .iter()
+        .flat_map(|(unit, content)| parse_socket_unit(unit, content))
+        .collect()
+}
+
+/// Extract the `Listen*=` lines of a `.socket` unit file
+pub fn parse_socket_unit(unit: &str, content: &str) -> Vec<SocketListen> {
+    let mut listens = Vec::new();
+    let mut in_socket_section = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_socket_section = line == "[Socket]";
+            continue;
+        }
+        if !in_socket_section || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let kind = match key.trim() {
+            "ListenStream" => ListenKind::Stream,
+            "ListenDatagram" => ListenKind::Datagram,
+            "ListenSequentialPacket" => ListenKind::SequentialPacket,
+            _ => continue,
+        };
+        let value = value.trim();
+        // An empty assignment resets the list built so far
+        if value.is_empty() {
+            listens.retain(|listen: &SocketListen| listen.kind != kind);
+            continue;
+        }
+        if let Some(endpoint) = parse_listen_value(value) {
+            listens.push(SocketListen {
+                unit: unit.to_string(),
+                kind,
+                endpoint,
+            });
+        }
+    }
+    listens
+}
+
+/// `80`, `127.0.0.1:80`, `[::1]:80`, `/run/foo.sock` or `@abstract`
+fn parse_listen_value(value: &str) -> Option<ListenEndpoint> {
+    if value.starts_with('/') || value.starts_with('@') {
+        return Some(ListenEndpoint::Path(value.to_string()));
+    }
+    if let Ok(port) = value.parse() {
+        return Some(ListenEndpoint::Port { address: None, port });
+    }
+    let (address, port) = value.rsplit_once(':')?;
+    Some(ListenEndpoint::Port {
+        address: Some(address.trim_start_matches('[').trim_end_matches(']').to_string()),
+        port: port.parse().ok()?,
+    })
+}
+
+fn match_socket_unit<'a>(listens: &'a [SocketListen], conn: &ConnectionInfo) -> Option<&'a str> {
+    listens
+        .iter()
+        .find(|listen| {
+            let kind_matches = matches!(
+                (&conn.protocol, listen.kind),
+                (Protocol::TCP, ListenKind::Stream)
+                    | (Protocol::UDP, ListenKind::Datagram)
+                    | (Protocol::Unix(UnixSocketType::Stream), ListenKind::Stream)
+                    | (Protocol::Unix(UnixSocketType::Datagram), ListenKind::Datagram)
+                    | (Protocol::Unix(UnixSocketType::SeqPacket), ListenKind::SequentialPacket)
+            );
+            kind_matches
+                && match &listen.endpoint {
+                    ListenEndpoint::Path(path) => matches!(conn.protocol, Protocol::Unix(_)) && *path == conn.local_address,
+                    ListenEndpoint::Port { address, port } => {
+                        *port == conn.local_port
+                            && address.as_deref().is_none_or(|address| address == conn.local_address)
+                    }
+                }
+        })
+        .map(|listen| listen.unit.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    #[test]
+    fn test_unit_from_cgroup() {
+        assert_eq!(unit_from_cgroup("/system.slice/nginx.service").as_deref(), Some("nginx.service"));
+        assert_eq!(
+            unit_from_cgroup("/user.slice/user-1000.slice/user@1000.service/app.slice/syncthing.service").as_deref(),
+            Some("syncthing.service")
+        );
+        assert_eq!(
+            unit_from_cgroup("/user.slice/user-1000.slice/session-2.scope").as_deref(),
+            Some("session-2.scope")
+        );
+        assert_eq!(unit_from_cgroup("/"), None);
+    }
+
+    #[test]
+    fn test_parse_socket_unit() {
+        let content = "\
+[Unit]
+Description=OpenBSD Secure Shell server socket
+
+[Socket]
+ListenStream=22
+ListenStream=[::1]:2222
+ListenDatagram=/run/systemd/journal/syslog
+# ListenStream=8080
+Accept=yes
+
+[Install]
+WantedBy=sockets.target
+";
+        let listens = parse_socket_unit("ssh.socket", content);
+        assert_eq!(listens.len(), 3);
+        assert_eq!(listens[0].endpoint, ListenEndpoint::Port { address: None, port: 22 });
+        assert_eq!(
+            listens[1].endpoint,
+            ListenEndpoint::Port { address: Some("::1".to_string()), port: 2222 }
+        );
+        assert_eq!(listens[2].kind, ListenKind::Datagram);
+
+        let conn = ConnectionInfo::new(
+            Protocol::TCP,
+            "0.0.0.0".to_string(),
+            22,
+            "*".to_string(),
+            0,
+            ConnectionState::Listening,
+            1,
+            "systemd".to_string(),
+        );
+        assert_eq!(match_socket_unit(&listens, &conn), Some("ssh.socket"));
+    }
+
+    #[test]
+    fn test_unknown_owner_matches_socket_unit() {
+        // Without root, listeners of other users are reported with pid 0
+        let mut connections = vec![ConnectionInfo::new(
+            Protocol::TCP,
+            "0.0.0.0".to_string(),
+            22,
+            "*".to_string(),
+            0,
+            ConnectionState::Listening,
+            0,
+            "".to_string(),
+        )];
+        let listens = parse_socket_unit("ssh.socket", "[Socket]\nListenStream=22\n");
+        assert_eq!(match_socket_unit(&listens, &connections[0]), Some("ssh.socket"));
+
+        // A process whose cgroup names its service keeps that service
+        connections[0].pid = 812;
+        connections[0].context.cgroup = Some("/system.slice/sshd.service".to_string());
+        SystemdEnricher::new().enrich(&mut connections);
+        assert_eq!(connections[0].context.systemd_unit.as_deref(), Some("sshd.service"));
+    }
+}
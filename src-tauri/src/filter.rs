@@ -1,15 +1,112 @@
-use crate::models::{ConnectionInfo, Protocol};
+use crate::models::{ConnectionInfo, Protocol, UnknownProtocol};
+use serde::{Deserialize, Serialize};
+
+/// Additional predicates applied on top of the protocol/port filter
+///
+/// Every criterion is optional; an empty `FilterCriteria` matches everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterCriteria {
+    /// Case-insensitive substring of the process name
+    pub process: Option<String>,
+    /// Only sockets with unread data in the receive queue
+    pub recv_queue_non_empty: bool,
+    /// Only sockets with unsent/unacknowledged data in the send queue
+    pub send_queue_non_empty: bool,
+    /// Only sockets that have retransmitted at least once
+    pub retransmitting: bool,
+    /// Only sockets owned by this uid
+    pub uid: Option<u32>,
+    /// Case-insensitive substring of the owning systemd unit
+    pub unit: Option<String>,
+    /// Case-insensitive substring of the well-known service name
+    pub service: Option<String>,
+    /// Interface name (`wg0`) or kind (`tunnel`, `wifi`); wildcard binds never match
+    pub interface: Option<String>,
+}
+
+impl FilterCriteria {
+    pub fn matches(&self, conn: &ConnectionInfo) -> bool {
+        if let Some(process) = &self.process {
+            if !conn.process_name.to_lowercase().contains(&process.to_lowercase()) {
+                return false;
+            }
+        }
+        if self.recv_queue_non_empty && conn.socket.rx_queue.unwrap_or(0) == 0 {
+            return false;
+        }
+        if self.send_queue_non_empty && conn.socket.tx_queue.unwrap_or(0) == 0 {
+            return false;
+        }
+        if self.retransmitting && conn.socket.retransmits.unwrap_or(0) == 0 {
+            return false;
+        }
+        if let Some(uid) = self.uid {
+            if conn.socket.uid != Some(uid) {
+                return false;
+            }
+        }
+        if let Some(service) = &self.service {
+            let is_service = conn
+                .service_name
+                .as_ref()
+                .is_some_and(|name| name.to_lowercase().contains(&service.to_lowercase()));
+            if !is_service {
+                return false;
+            }
+        }
+        if let Some(interface) = &self.interface {
+            let on_interface = conn.interface.as_ref().is_some_and(|info| {
+                info.name.eq_ignore_ascii_case(interface) || info.kind.as_str().eq_ignore_ascii_case(interface)
+            });
+            if !on_interface {
+                return false;
+            }
+        }
+        if let Some(unit) = &self.unit {
+            let owned_by_unit = conn
+                .context
+                .systemd_unit
+                .as_ref()
+                .is_some_and(|name| name.to_lowercase().contains(&unit.to_lowercase()));
+            if !owned_by_unit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Check a protocol filter: `all`, `other` or any name `Protocol` parses
+pub fn validate_protocol_filter(protocol: &str) -> Result<(), UnknownProtocol> {
+    match protocol {
+        "all" | "other" => Ok(()),
+        name => name.parse::<Protocol>().map(|_| ()),
+    }
+}
+
+fn protocol_matches(conn_protocol: &Protocol, filter: &str) -> bool {
+    match filter {
+        "all" => true,
+        "other" => matches!(conn_protocol, Protocol::Other(_)),
+        name => name.parse::<Protocol>().is_ok_and(|p| p.same_kind(conn_protocol)),
+    }
+}
 
 pub fn filter_connections(connections: &[ConnectionInfo], protocol: &str, filter_port: Option<u16>) -> Vec<ConnectionInfo> {
+    filter_connections_with(connections, protocol, filter_port, &FilterCriteria::default())
+}
+
+pub fn filter_connections_with(
+    connections: &[ConnectionInfo],
+    protocol: &str,
+    filter_port: Option<u16>,
+    criteria: &FilterCriteria,
+) -> Vec<ConnectionInfo> {
     connections.iter()
         .filter(|conn| {
             // Filter by protocol
-            let protocol_match = match protocol {
-                "tcp" => conn.protocol == Protocol::TCP,
-                "udp" => conn.protocol == Protocol::UDP,
-                "all" => true,
-                _ => false,
-            };
+            let protocol_match = protocol_matches(&conn.protocol, protocol);
             
             // Filter by port if specified (using string prefix matching)
             let port_match = if let Some(port) = filter_port {
@@ -20,7 +117,7 @@ pub fn filter_connections(connections: &[ConnectionInfo], protocol: &str, filter
                 true
             };
             
-            protocol_match && port_match
+            protocol_match && port_match && criteria.matches(conn)
         })
         .cloned()
         .collect()
@@ -29,6 +126,7 @@ pub fn filter_connections(connections: &[ConnectionInfo], protocol: &str, filter
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{ConnectionState, SocketDetails, UnixSocketType};
 
     #[test]
     fn test_filter_by_protocol() {
@@ -65,6 +163,21 @@ mod tests {
 
         let all_filtered = filter_connections(&connections, "all", None);
         assert_eq!(all_filtered.len(), 2);
+
+        assert!(filter_connections(&connections, "sctp", None).is_empty());
+        assert!(validate_protocol_filter("sctp").is_ok());
+
+        // "unix" names the family, whatever the socket type
+        let unix: Vec<ConnectionInfo> = [UnixSocketType::Stream, UnixSocketType::Datagram, UnixSocketType::SeqPacket]
+            .into_iter()
+            .map(|socket_type| ConnectionInfo::new(Protocol::Unix(socket_type), "/run/test.sock".to_string(), 0, "".to_string(), 0, ConnectionState::Unknown(0), 1, "test".to_string()))
+            .collect();
+        assert_eq!(filter_connections(&unix, "unix", None).len(), 3);
+        assert!(filter_connections(&unix, "tcp", None).is_empty());
+        assert_eq!(
+            validate_protocol_filter("quic"),
+            Err(UnknownProtocol("quic".to_string()))
+        );
     }
 
     #[test]
@@ -103,4 +216,35 @@ mod tests {
         let port_999_filtered = filter_connections(&connections, "all", Some(999));
         assert_eq!(port_999_filtered.len(), 0);
     }
+
+    #[test]
+    fn test_filter_by_socket_queues() {
+        let idle = ConnectionInfo::new(
+            Protocol::TCP,
+            "127.0.0.1".to_string(),
+            5432,
+            "127.0.0.1".to_string(),
+            50000,
+            ConnectionState::Established,
+            1234,
+            "postgres".to_string(),
+        );
+        let stuck = idle.clone().with_socket_details(SocketDetails {
+            rx_queue: Some(4096),
+            retransmits: Some(3),
+            ..SocketDetails::default()
+        });
+        let connections = vec![idle, stuck];
+
+        let criteria = FilterCriteria { recv_queue_non_empty: true, ..FilterCriteria::default() };
+        let filtered = filter_connections_with(&connections, "all", None, &criteria);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].socket.rx_queue, Some(4096));
+
+        let criteria = FilterCriteria { retransmitting: true, process: Some("POST".to_string()), ..FilterCriteria::default() };
+        assert_eq!(filter_connections_with(&connections, "tcp", None, &criteria).len(), 1);
+
+        let criteria = FilterCriteria { send_queue_non_empty: true, ..FilterCriteria::default() };
+        assert!(filter_connections_with(&connections, "all", None, &criteria).is_empty());
+    }
 }
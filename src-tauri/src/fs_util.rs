@@ -0,0 +1,179 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+/// Write through a temp file and rename so readers never see a partial file
+pub fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+/// Reading or writing a `JsonStore` file failed
+#[derive(Debug, Clone)]
+pub struct StoreError(pub String);
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// Where a store (`JsonStore`, the settings) writes its changes
+pub enum Backing {
+    /// Not loaded: changes stay in memory
+    Memory,
+    File(PathBuf),
+    /// The file could not be read or is from a newer version; saving would destroy it
+    ReadOnly(String),
+}
+
+/// A list kept in memory and mirrored to a `{"version": n, "<key>": [...]}` file
+///
+/// `load` points the store at a file; from then on every change is written
+/// back before it becomes visible. A file that does not parse is copied to
+/// `<path>.bak` and replaced on the next change. A file from a newer version
+/// is backed up too, and the store refuses to save over it.
+pub struct JsonStore<T> {
+    key: &'static str,
+    version: u32,
+    backing: Mutex<Backing>,
+    items: RwLock<Vec<T>>,
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> JsonStore<T> {
+    pub fn new(key: &'static str, version: u32) -> Self {
+        Self {
+            key,
+            version,
+            backing: Mutex::new(Backing::Memory),
+            items: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Read items from `path`, keeping those `keep` accepts; a missing file means none
+    pub fn load(&self, path: PathBuf, keep: impl FnMut(&T) -> bool) -> Result<usize, StoreError> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                *self.backing.lock().unwrap() = Backing::File(path);
+                return Ok(0);
+            }
+            Err(e) => return Err(self.refuse_saving(format!("{}: {}", path.display(), e))),
+        };
+
+        let document: Map<String, Value> = match serde_json::from_str(&content) {
+            Ok(document) => document,
+            Err(e) => return Err(self.back_up(path, e.to_string())),
+        };
+        let version = document.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > self.version as u64 {
+            let _ = fs::copy(&path, path.with_extension("json.bak"));
+            return Err(self.refuse_saving(format!(
+                "{} was written by a newer version (format {})",
+                path.display(),
+                version
+            )));
+        }
+        let items: Vec<T> = match document.get(self.key).cloned().map(serde_json::from_value) {
+            Some(Ok(items)) => items,
+            Some(Err(e)) => return Err(self.back_up(path, e.to_string())),
+            None => return Err(self.back_up(path, format!("missing \"{}\"", self.key))),
+        };
+
+        let items: Vec<T> = items.into_iter().filter(keep).collect();
+        let count = items.len();
+        *self.items.write().unwrap() = items;
+        *self.backing.lock().unwrap() = Backing::File(path);
+        Ok(count)
+    }
+
+    pub fn items(&self) -> Vec<T> {
+        self.items.read().unwrap().clone()
+    }
+
+    /// Apply `change` to a copy of the items and save it; if either fails nothing changes
+    pub fn update<R, E: From<StoreError>>(&self, change: impl FnOnce(&mut Vec<T>) -> Result<R, E>) -> Result<R, E> {
+        let mut items = self.items.write().unwrap();
+        let mut updated = items.clone();
+        let result = change(&mut updated)?;
+        self.save(&updated)?;
+        *items = updated;
+        Ok(result)
+    }
+
+    /// Keep the unparseable file as `<path>.bak`; the next save replaces it
+    fn back_up(&self, path: PathBuf, error: String) -> StoreError {
+        let _ = fs::copy(&path, path.with_extension("json.bak"));
+        let message = format!("{}: {}", path.display(), error);
+        *self.backing.lock().unwrap() = Backing::File(path);
+        StoreError(message)
+    }
+
+    fn refuse_saving(&self, message: String) -> StoreError {
+        *self.backing.lock().unwrap() = Backing::ReadOnly(message.clone());
+        StoreError(message)
+    }
+
+    fn save(&self, items: &[T]) -> Result<(), StoreError> {
+        let path = match &*self.backing.lock().unwrap() {
+            Backing::Memory => return Ok(()),
+            Backing::File(path) => path.clone(),
+            Backing::ReadOnly(reason) => return Err(StoreError(format!("not saving over the file: {}", reason))),
+        };
+        let mut document = Map::new();
+        document.insert("version".to_string(), self.version.into());
+        document.insert(self.key.to_string(), serde_json::to_value(items).map_err(|e| StoreError(e.to_string()))?);
+        let json = serde_json::to_string_pretty(&document).map_err(|e| StoreError(e.to_string()))?;
+        write_atomic(&path, &json).map_err(|e| StoreError(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("portviewer-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_newer_file_is_never_overwritten() {
+        let path = temp_path("store-newer");
+        let newer = r#"{ "version": 9, "items": [{ "shape": "unknown" }] }"#;
+        fs::write(&path, newer).unwrap();
+
+        let store: JsonStore<String> = JsonStore::new("items", 1);
+        assert!(store.load(path.clone(), |_| true).is_err());
+        let saved = store.update(|items| {
+            items.push("new".to_string());
+            Ok::<_, StoreError>(())
+        });
+        assert!(saved.is_err());
+        assert!(store.items().is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+
+        // A corrupt file is kept as .bak and replaced by the next change
+        fs::write(&path, "{ not json").unwrap();
+        assert!(store.load(path.clone(), |_| true).is_err());
+        store.update(|items| {
+            items.push("kept".to_string());
+            Ok::<_, StoreError>(())
+        }).unwrap();
+        assert_eq!(store.load(path.clone(), |_| true).unwrap(), 1);
+        assert_eq!(fs::read_to_string(path.with_extension("json.bak")).unwrap(), "{ not json");
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(path.with_extension("json.bak")).unwrap();
+    }
+}
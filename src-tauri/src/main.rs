@@ -4,11 +4,41 @@
 mod models;
 mod filter;
 mod platform;
+mod collector;
+mod enrich;
+mod settings;
+mod fs_util;
+mod ports;
+mod wait;
+mod monitor;
+mod baseline;
+mod remotes;
+mod cli;
 
-use models::ConnectionInfo;
+use models::{ConnectionInfo, ConnectionLabel, Finding, ForwardTarget, InterfaceInfo, PeerInfo, ProcessContext, Protocol, SocketDetails};
 use platform::create_network_provider;
-use filter::filter_connections;
+use filter::{filter_connections, filter_connections_with, validate_protocol_filter, FilterCriteria};
+use collector::ConnectionCollector;
+use enrich::geoip::GeoIpDatabaseInfo;
+use enrich::interfaces::NetworkInterface;
+use enrich::labels::{LabelRule, LABELS_FILE};
+use enrich::services::SERVICE_OVERRIDES_FILE;
+use settings::{Settings, SettingsStore, SETTINGS_FILE};
+use ports::{PortRange, PortUsageReport};
+use wait::{WaitCondition, WaitCriteria, WaitOutcome};
+use monitor::Monitor;
+use baseline::{Baseline, BaselineStore, DriftReport, BASELINES_FILE};
+use remotes::{GroupBy, GroupSort, RemoteGroup};
+use monitor::history::{HistoryEvent, HistoryQuery, HISTORY_DIR};
+use monitor::ConnectionRecord;
+use monitor::throughput::ThroughputStats;
+use monitor::trends::LeakAlert;
+use monitor::rules::{AlertEvent, AlertRule, ALERT_RULES_FILE};
+use platform::unified::{BackendKind, DiagnosticsReport};
 use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use std::time::Duration;
 use uuid::Uuid;
 
 // Serializable version of ConnectionInfo for Tauri commands
@@ -16,6 +46,8 @@ use uuid::Uuid;
 struct ConnectionInfoSerde {
     id: String,
     protocol: String,
+    /// STREAM/DGRAM/SEQPACKET for Unix domain sockets
+    socket_type: Option<String>,
     local_address: String,
     local_port: u16,
     remote_address: String,
@@ -23,6 +55,15 @@ struct ConnectionInfoSerde {
     state: String,
     pid: u32,
     process_name: String,
+    socket: SocketDetails,
+    context: ProcessContext,
+    forward: Option<ForwardTarget>,
+    service_name: Option<String>,
+    remote_hostname: Option<String>,
+    remote_peer: Option<PeerInfo>,
+    labels: Vec<ConnectionLabel>,
+    interface: Option<InterfaceInfo>,
+    findings: Vec<Finding>,
 }
 
 // Generate unique ID for connections using UUID
@@ -32,9 +73,14 @@ fn generate_connection_id() -> String {
 
 impl From<ConnectionInfo> for ConnectionInfoSerde {
     fn from(conn: ConnectionInfo) -> Self {
+        let socket_type = match &conn.protocol {
+            Protocol::Unix(kind) => Some(kind.to_string()),
+            _ => None,
+        };
         ConnectionInfoSerde {
             id: generate_connection_id(),
             protocol: conn.protocol.to_string(),
+            socket_type,
             local_address: conn.local_address,
             local_port: conn.local_port,
             remote_address: conn.remote_address,
@@ -42,6 +88,15 @@ impl From<ConnectionInfo> for ConnectionInfoSerde {
             state: conn.state.to_string(),
             pid: conn.pid,
             process_name: conn.process_name,
+            socket: conn.socket,
+            context: conn.context,
+            forward: conn.forward,
+            service_name: conn.service_name,
+            remote_hostname: conn.remote_hostname,
+            remote_peer: conn.remote_peer,
+            labels: conn.labels,
+            interface: conn.interface,
+            findings: conn.findings,
         }
     }
 }
@@ -62,43 +117,57 @@ fn log_message(level: String, message: String, data: Option<String>) {
 }
 
 // Tauri command to get all connections using cross-platform provider
+//
+// Collection runs off the UI thread; a newer call supersedes an older one,
+// which then fails with a "Request cancelled" error the frontend can ignore.
 #[tauri::command]
-fn get_connections() -> Vec<ConnectionInfoSerde> {
-    let provider = create_network_provider();
-    println!("Backend: Using {} network provider", provider.platform_name());
+async fn get_connections(collector: State<'_, ConnectionCollector>, monitor: State<'_, Monitor>) -> Result<Vec<ConnectionInfoSerde>, String> {
+    println!("Backend: Using {} network provider", create_network_provider().platform_name());
     
-    match provider.get_all_connections() {
-        Ok(connections) => {
+    match collector.collect("get_connections").await {
+        Ok(mut connections) => {
             println!("Backend: Retrieved {} total connections", connections.len());
+            monitor.throughput().annotate(&mut connections);
             
-            let tcp_count = connections.iter().filter(|c| matches!(c.protocol, crate::models::Protocol::TCP)).count();
-            let udp_count = connections.iter().filter(|c| matches!(c.protocol, crate::models::Protocol::UDP)).count();
-            println!("Backend: TCP connections: {}, UDP connections: {}", tcp_count, udp_count);
+            let tcp_count = connections.iter().filter(|c| matches!(c.protocol, Protocol::TCP)).count();
+            let udp_count = connections.iter().filter(|c| matches!(c.protocol, Protocol::UDP)).count();
+            let unix_count = connections.iter().filter(|c| matches!(c.protocol, Protocol::Unix(_))).count();
+            println!("Backend: TCP connections: {}, UDP connections: {}, Unix sockets: {}", tcp_count, udp_count, unix_count);
             
             let result: Vec<ConnectionInfoSerde> = connections.into_iter().map(ConnectionInfoSerde::from).collect();
             println!("Backend: Returning {} serialized connections", result.len());
-            result
+            Ok(result)
         }
         Err(e) => {
             eprintln!("Backend Error: Failed to get connections: {}", e);
-            Vec::new()
+            Err(e.to_string())
         }
     }
 }
 
 // Tauri command to get filtered connections
 #[tauri::command]
-fn get_filtered_connections(protocol: String, port: Option<u16>) -> Vec<ConnectionInfoSerde> {
-    let provider = create_network_provider();
-    
-    match provider.get_all_connections() {
-        Ok(all_connections) => {
-            let filtered = filter_connections(&all_connections, &protocol, port);
-            filtered.into_iter().map(ConnectionInfoSerde::from).collect()
+async fn get_filtered_connections(
+    collector: State<'_, ConnectionCollector>,
+    monitor: State<'_, Monitor>,
+    protocol: String,
+    port: Option<u16>,
+    criteria: Option<FilterCriteria>,
+) -> Result<Vec<ConnectionInfoSerde>, String> {
+    validate_protocol_filter(&protocol).map_err(|e| e.to_string())?;
+
+    match collector.collect("get_filtered_connections").await {
+        Ok(mut all_connections) => {
+            monitor.throughput().annotate(&mut all_connections);
+            let filtered = match criteria {
+                Some(criteria) => filter_connections_with(&all_connections, &protocol, port, &criteria),
+                None => filter_connections(&all_connections, &protocol, port),
+            };
+            Ok(filtered.into_iter().map(ConnectionInfoSerde::from).collect())
         }
         Err(e) => {
             eprintln!("Backend Error: Failed to get connections for filtering: {}", e);
-            Vec::new()
+            Err(e.to_string())
         }
     }
 }
@@ -115,6 +184,397 @@ fn get_platform_info() -> serde_json::Value {
     })
 }
 
+// Tauri command reporting which backends exist and how their last runs went
+//
+// Support probes run commands like `lsof -v`, so diagnostics are built on the
+// blocking pool rather than the UI thread.
+#[tauri::command]
+async fn get_backend_diagnostics(app: tauri::AppHandle) -> Result<DiagnosticsReport, String> {
+    backend_diagnostics(app).await
+}
+
+// Tauri command to force a specific backend (`null` restores the automatic chain)
+#[tauri::command]
+async fn set_backend_preference(
+    app: tauri::AppHandle,
+    collector: State<'_, ConnectionCollector>,
+    settings: State<'_, SettingsStore>,
+    backend: Option<BackendKind>,
+) -> Result<DiagnosticsReport, String> {
+    collector.provider().set_forced_backend(backend).map_err(|e| e.to_string())?;
+    println!("Backend: preference set to {:?}", backend);
+    persist_setting(&settings, |s| s.backend = backend);
+    backend_diagnostics(app).await
+}
+
+// Tauri command to include sockets from every network namespace (containers)
+#[tauri::command]
+async fn set_namespace_scan(
+    app: tauri::AppHandle,
+    collector: State<'_, ConnectionCollector>,
+    settings: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<DiagnosticsReport, String> {
+    collector.provider().set_all_namespaces(enabled);
+    println!("Backend: all-namespace scan {}", if enabled { "enabled" } else { "disabled" });
+    persist_setting(&settings, |s| s.all_namespaces = enabled);
+    backend_diagnostics(app).await
+}
+
+async fn backend_diagnostics(app: tauri::AppHandle) -> Result<DiagnosticsReport, String> {
+    tokio::task::spawn_blocking(move || app.state::<ConnectionCollector>().provider().diagnostics())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Tauri command to toggle background reverse-DNS lookups of remote addresses
+#[tauri::command]
+fn set_reverse_dns(
+    collector: State<'_, ConnectionCollector>,
+    settings: State<'_, SettingsStore>,
+    monitor: State<'_, Monitor>,
+    enabled: bool,
+) -> bool {
+    collector.enrichment().reverse_dns().set_enabled(enabled);
+    monitor.rules().set_reverse_dns(enabled);
+    println!("Backend: reverse DNS {}", if enabled { "enabled" } else { "disabled" });
+    persist_setting(&settings, |s| s.reverse_dns = enabled);
+    enabled
+}
+
+#[tauri::command]
+fn get_reverse_dns(collector: State<'_, ConnectionCollector>) -> bool {
+    collector.enrichment().reverse_dns().is_enabled()
+}
+
+// Tauri command for throughput totals and top talkers as of the last monitor tick
+#[tauri::command]
+fn get_throughput_stats(monitor: State<'_, Monitor>) -> ThroughputStats {
+    monitor.throughput().stats()
+}
+
+// Tauri command to list local interfaces, for the interface filter
+#[tauri::command]
+fn get_interfaces() -> Result<Vec<NetworkInterface>, String> {
+    enrich::interfaces::list_interfaces().map_err(|e| e.to_string())
+}
+
+// ASN/GeoIP database commands; lookups only ever read these local files
+#[tauri::command]
+fn get_geoip_databases(collector: State<'_, ConnectionCollector>) -> Vec<GeoIpDatabaseInfo> {
+    collector.enrichment().geoip().databases()
+}
+
+// Databases can be tens of megabytes, so they are read on the blocking pool
+#[tauri::command]
+async fn set_geoip_databases(app: tauri::AppHandle, paths: Vec<String>) -> Result<Vec<GeoIpDatabaseInfo>, String> {
+    let paths: Vec<String> = paths.into_iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+    tokio::task::spawn_blocking(move || {
+        let databases = app.state::<ConnectionCollector>().enrichment().geoip().set_paths(&paths).map_err(|e| e.to_string())?;
+        println!("Backend: {} GeoIP database(s) loaded", databases.len());
+        persist_setting(&app.state::<SettingsStore>(), |s| s.geoip_databases = paths);
+        Ok(databases)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Label rule commands; every change is saved to the app data dir right away
+#[tauri::command]
+fn get_label_rules(collector: State<'_, ConnectionCollector>) -> Vec<LabelRule> {
+    collector.enrichment().labels().rules()
+}
+
+#[tauri::command]
+fn create_label_rule(collector: State<'_, ConnectionCollector>, rule: LabelRule) -> Result<LabelRule, String> {
+    collector.enrichment().labels().create(rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_label_rule(collector: State<'_, ConnectionCollector>, rule: LabelRule) -> Result<LabelRule, String> {
+    collector.enrichment().labels().update(rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_label_rule(collector: State<'_, ConnectionCollector>, id: String) -> Result<(), String> {
+    collector.enrichment().labels().delete(&id).map_err(|e| e.to_string())
+}
+
+// Tauri command listing ports nobody uses, confirmed by actually binding them
+#[tauri::command]
+async fn find_free_ports(
+    collector: State<'_, ConnectionCollector>,
+    protocol: String,
+    address: String,
+    range: PortRange,
+    count: usize,
+) -> Result<Vec<u16>, String> {
+    let protocol = protocol.parse::<Protocol>().map_err(|e| e.to_string())?;
+    let address = ports::parse_bind_address(&address).ok_or_else(|| format!("'{}' is not an IP address", address))?;
+    let connections = collector.snapshot().await.map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || ports::find_free_ports(&connections, &protocol, address, range, count))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+// Tauri command answering "who is using this port" and whether a bind would fail
+//
+// `address` defaults to the wildcard, the usual server bind; `reuse_addr`
+// describes the program that wants the port and defaults to true.
+#[tauri::command]
+async fn who_is_using_port(
+    collector: State<'_, ConnectionCollector>,
+    port: u16,
+    protocol: Option<String>,
+    address: Option<String>,
+    reuse_addr: Option<bool>,
+) -> Result<PortUsageReport, String> {
+    let protocol = protocol.as_deref().unwrap_or("tcp").parse::<Protocol>().map_err(|e| e.to_string())?;
+    let address_text = address.unwrap_or_else(|| "*".to_string());
+    let address = ports::parse_bind_address(&address_text).ok_or_else(|| format!("'{}' is not an IP address", address_text))?;
+    let connections = collector.snapshot().await.map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut report = ports::explain_port_usage(&connections, &protocol, address, port, reuse_addr.unwrap_or(true));
+        ports::confirm_with_bind(&mut report, &protocol, address);
+        report
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// Tauri commands blocking until a port is listened on / released, or the timeout passes
+//
+// The same waits are available headless: `portviewer wait-for-port 5432`.
+#[tauri::command]
+async fn wait_for_port(
+    collector: State<'_, ConnectionCollector>,
+    criteria: WaitCriteria,
+    timeout_ms: Option<u64>,
+    poll_interval_ms: Option<u64>,
+) -> Result<WaitOutcome, String> {
+    run_wait(&collector, WaitCondition::Listening, criteria, timeout_ms, poll_interval_ms).await
+}
+
+#[tauri::command]
+async fn wait_for_release(
+    collector: State<'_, ConnectionCollector>,
+    criteria: WaitCriteria,
+    timeout_ms: Option<u64>,
+    poll_interval_ms: Option<u64>,
+) -> Result<WaitOutcome, String> {
+    run_wait(&collector, WaitCondition::Released, criteria, timeout_ms, poll_interval_ms).await
+}
+
+async fn run_wait(
+    collector: &ConnectionCollector,
+    condition: WaitCondition,
+    criteria: WaitCriteria,
+    timeout_ms: Option<u64>,
+    poll_interval_ms: Option<u64>,
+) -> Result<WaitOutcome, String> {
+    let timeout = timeout_ms.map(Duration::from_millis).unwrap_or(wait::DEFAULT_WAIT_TIMEOUT);
+    let poll_interval = poll_interval_ms.map(Duration::from_millis).unwrap_or(wait::DEFAULT_POLL_INTERVAL);
+    println!("Backend: waiting up to {}s for port {} ({:?})", timeout.as_secs(), criteria.port, condition);
+    wait::wait_for(|| collector.snapshot(), condition, &criteria, timeout, poll_interval)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Tauri command returning recorded open/close/state-change events in a time range
+//
+// Both history queries read segment files, so they run on the blocking pool;
+// the `AppHandle` gives that thread its own access to the monitor.
+#[tauri::command]
+async fn query_history_events(app: tauri::AppHandle, query: HistoryQuery) -> Result<Vec<HistoryEvent>, String> {
+    tokio::task::spawn_blocking(move || app.state::<Monitor>().history().events(&query))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+// Tauri command reconstructing the connections that were open at a point in time
+#[tauri::command]
+async fn query_history_at(
+    app: tauri::AppHandle,
+    at: chrono::DateTime<chrono::Utc>,
+    port: Option<u16>,
+    process: Option<String>,
+) -> Result<Vec<ConnectionRecord>, String> {
+    let query = HistoryQuery {
+        port,
+        process,
+        ..HistoryQuery::default()
+    };
+    tokio::task::spawn_blocking(move || app.state::<Monitor>().history().connections_at(at, &query))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+// Tauri command listing processes whose socket counts look like a leak
+#[tauri::command]
+fn get_leak_alerts(monitor: State<'_, Monitor>) -> Vec<LeakAlert> {
+    monitor.trends().alerts()
+}
+
+// Alert rule commands; every change is saved to the app data dir right away
+#[tauri::command]
+fn get_alert_rules(monitor: State<'_, Monitor>) -> Vec<AlertRule> {
+    monitor.rules().rules()
+}
+
+#[tauri::command]
+fn create_alert_rule(monitor: State<'_, Monitor>, rule: AlertRule) -> Result<AlertRule, String> {
+    monitor.rules().create(rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn update_alert_rule(monitor: State<'_, Monitor>, rule: AlertRule) -> Result<AlertRule, String> {
+    monitor.rules().update(rule).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_alert_rule(monitor: State<'_, Monitor>, id: String) -> Result<(), String> {
+    monitor.rules().delete(&id).map_err(|e| e.to_string())
+}
+
+// Tauri command returning the rule violations firing now and the recent fired/resolved log
+#[tauri::command]
+fn get_alerts(monitor: State<'_, Monitor>) -> serde_json::Value {
+    serde_json::json!({
+        "active": monitor.rules().active(),
+        "log": monitor.rules().log()
+    })
+}
+
+// Listening-port baseline commands for hardening reviews
+#[tauri::command]
+fn get_baselines(baselines: State<'_, BaselineStore>) -> Vec<Baseline> {
+    baselines.baselines()
+}
+
+// Tauri command saving the current listeners as an approved baseline
+//
+// `protocol` and `criteria` narrow what is recorded, as in the connection filter.
+#[tauri::command]
+async fn capture_baseline(
+    collector: State<'_, ConnectionCollector>,
+    baselines: State<'_, BaselineStore>,
+    name: String,
+    protocol: Option<String>,
+    criteria: Option<FilterCriteria>,
+) -> Result<Baseline, String> {
+    let connections = collector.snapshot().await.map_err(|e| e.to_string())?;
+    baselines
+        .capture(&name, &connections, protocol.as_deref().unwrap_or("all"), &criteria.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_baseline(baselines: State<'_, BaselineStore>, id: String) -> Result<(), String> {
+    baselines.delete(&id).map_err(|e| e.to_string())
+}
+
+// Tauri command comparing the current listeners against a saved baseline
+#[tauri::command]
+async fn get_drift_report(
+    collector: State<'_, ConnectionCollector>,
+    baselines: State<'_, BaselineStore>,
+    id: String,
+) -> Result<DriftReport, String> {
+    let baseline = baselines.get(&id).map_err(|e| e.to_string())?;
+    let connections = collector.snapshot().await.map_err(|e| e.to_string())?;
+    Ok(baseline::drift_report(&baseline, &connections))
+}
+
+// Tauri command grouping remote endpoints by host or subnet with the processes talking to them
+#[tauri::command]
+async fn get_remote_groups(
+    collector: State<'_, ConnectionCollector>,
+    by: GroupBy,
+    protocol: Option<String>,
+    sort: Option<GroupSort>,
+    descending: Option<bool>,
+) -> Result<Vec<RemoteGroup>, String> {
+    let protocol = protocol.unwrap_or_else(|| "all".to_string());
+    validate_protocol_filter(&protocol).map_err(|e| e.to_string())?;
+    let connections = collector.snapshot().await.map_err(|e| e.to_string())?;
+    let connections = filter_connections(&connections, &protocol, None);
+    Ok(remotes::group_remotes(&connections, by, sort.unwrap_or_default(), descending.unwrap_or(true)))
+}
+
+// Tauri command returning the persisted user settings
+#[tauri::command]
+fn get_settings(settings: State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+// Tauri command replacing all settings; invalid values are rejected, not clamped
+#[tauri::command]
+fn set_settings(app: tauri::AppHandle, settings: State<'_, SettingsStore>, new_settings: Settings) -> Result<Settings, String> {
+    let saved = settings.set(new_settings).map_err(|e| e.to_string())?;
+    apply_settings(&app, &saved);
+    Ok(saved)
+}
+
+/// Push the backend-side settings into the collector and monitor
+fn apply_settings(app: &tauri::AppHandle, settings: &Settings) {
+    let collector = app.state::<ConnectionCollector>();
+    let monitor = app.state::<Monitor>();
+    if let Err(e) = collector.provider().set_forced_backend(settings.backend) {
+        eprintln!("Backend Error: saved backend preference not applied: {}", e);
+    }
+    collector.provider().set_all_namespaces(settings.all_namespaces);
+    collector.enrichment().reverse_dns().set_enabled(settings.reverse_dns);
+    monitor.rules().set_reverse_dns(settings.reverse_dns);
+    // Databases can be large, so only reload when the list changed, and off this thread
+    let loaded: Vec<String> = collector.enrichment().geoip().databases().into_iter().map(|db| db.path).collect();
+    if loaded != settings.geoip_databases {
+        let app = app.clone();
+        let paths = settings.geoip_databases.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = app.state::<ConnectionCollector>().enrichment().geoip().set_paths(&paths) {
+                eprintln!("Backend Error: {}", e);
+            }
+        });
+    }
+    monitor.history().configure(settings.history_enabled, settings.history_retention_days, settings.monitor_interval_secs);
+    monitor.trends().set_thresholds(settings.leak_detection.clone());
+}
+
+/// Background loop feeding snapshots to the monitor until the app exits
+///
+/// Uses `snapshot()` so it never cancels, or is cancelled by, the table
+/// refresh. The interval is re-read every tick so changing it takes effect
+/// without a restart.
+async fn run_monitor(app: tauri::AppHandle) {
+    loop {
+        let interval = app.state::<SettingsStore>().get().monitor_interval_secs;
+        match app.state::<ConnectionCollector>().snapshot().await {
+            Ok(connections) => {
+                let handle = app.clone();
+                let at = chrono::Utc::now();
+                let ticked = tokio::task::spawn_blocking(move || handle.state::<Monitor>().tick(at, &connections)).await;
+                if let Err(e) = ticked {
+                    eprintln!("Backend Error: monitor tick failed: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Backend Error: monitor snapshot failed: {}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(interval as u64)).await;
+    }
+}
+
+/// Record a toggle made through a dedicated command; failing to save is not fatal
+fn persist_setting(settings: &SettingsStore, change: impl FnOnce(&mut Settings)) {
+    if let Err(e) = settings.update(change) {
+        eprintln!("Backend Error: {}", e);
+    }
+}
+
 // Window control commands
 #[tauri::command]
 async fn minimize_window(window: tauri::Window) -> Result<(), String> {
@@ -137,12 +597,107 @@ async fn close_window(window: tauri::Window) -> Result<(), String> {
 }
 
 fn main() {
+    if let Some(code) = cli::run_from_args() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .manage(ConnectionCollector::new())
+        .manage(SettingsStore::new())
+        .manage(Monitor::new())
+        .manage(BaselineStore::new())
+        .setup(|app| {
+            let collector = app.state::<ConnectionCollector>();
+            let settings = app.state::<SettingsStore>();
+            let monitor = app.state::<Monitor>();
+            match app.path().app_config_dir() {
+                Ok(dir) => {
+                    if let Err(e) = settings.load(dir.join(SETTINGS_FILE)) {
+                        eprintln!("Backend Error: {}, using defaults", e);
+                    }
+                    collector.enrichment().service_overrides().set_path(dir.join(SERVICE_OVERRIDES_FILE));
+                }
+                Err(e) => eprintln!("Backend Error: no app config dir, settings will not persist: {}", e),
+            }
+            apply_settings(app.handle(), &settings.get());
+
+            match app.path().app_data_dir() {
+                Ok(dir) => {
+                    match collector.enrichment().labels().load(dir.join(LABELS_FILE)) {
+                        Ok(count) => println!("Backend: loaded {} label rules", count),
+                        Err(e) => eprintln!("Backend Error: {}", e),
+                    }
+                    match monitor.rules().load(dir.join(ALERT_RULES_FILE)) {
+                        Ok(count) => println!("Backend: loaded {} alert rules", count),
+                        Err(e) => eprintln!("Backend Error: {}", e),
+                    }
+                    match app.state::<BaselineStore>().load(dir.join(BASELINES_FILE)) {
+                        Ok(count) => println!("Backend: loaded {} listener baselines", count),
+                        Err(e) => eprintln!("Backend Error: {}", e),
+                    }
+                    monitor.history().set_dir(dir.join(HISTORY_DIR));
+                }
+                Err(e) => eprintln!("Backend Error: no app data dir, rules and history will not persist: {}", e),
+            }
+
+            // Notify from here so alerts show even while the window is hidden;
+            // the event only updates the UI
+            let handle = app.handle().clone();
+            monitor.rules().set_notifier(Box::new(move |event: &AlertEvent| {
+                let title = if event.resolved {
+                    format!("Resolved: {}", event.rule_name)
+                } else {
+                    event.rule_name.clone()
+                };
+                if let Err(e) = handle.notification().builder().title(title).body(&event.message).show() {
+                    eprintln!("Backend Error: failed to show alert notification: {}", e);
+                }
+                if let Err(e) = handle.emit("alert", event) {
+                    eprintln!("Backend Error: failed to deliver alert: {}", e);
+                }
+            }));
+
+            tauri::async_runtime::spawn(run_monitor(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_connections, 
             get_filtered_connections, 
             log_message, 
             get_platform_info,
+            get_backend_diagnostics,
+            set_backend_preference,
+            set_namespace_scan,
+            get_reverse_dns,
+            set_reverse_dns,
+            get_interfaces,
+            get_throughput_stats,
+            get_geoip_databases,
+            set_geoip_databases,
+            get_label_rules,
+            create_label_rule,
+            update_label_rule,
+            delete_label_rule,
+            find_free_ports,
+            who_is_using_port,
+            wait_for_port,
+            wait_for_release,
+            query_history_events,
+            query_history_at,
+            get_leak_alerts,
+            get_alert_rules,
+            create_alert_rule,
+            update_alert_rule,
+            delete_alert_rule,
+            get_alerts,
+            get_baselines,
+            capture_baseline,
+            delete_baseline,
+            get_drift_report,
+            get_remote_groups,
+            get_settings,
+            set_settings,
             minimize_window,
             toggle_maximize,
             close_window
@@ -1,7 +1,18 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Protocol {
     TCP,
     UDP,
+    Unix(UnixSocketType),
+    SCTP,
+    /// Raw IP socket; the port fields carry the IP protocol number
+    Raw,
+    /// Unprivileged ICMP "ping" socket; the local port is the echo identifier
+    ICMP,
+    /// Transport reported by a backend that has no dedicated variant
+    Other(String),
 }
 
 impl std::fmt::Display for Protocol {
@@ -9,16 +20,69 @@ impl std::fmt::Display for Protocol {
         match self {
             Protocol::TCP => write!(f, "TCP"),
             Protocol::UDP => write!(f, "UDP"),
+            Protocol::Unix(_) => write!(f, "UNIX"),
+            Protocol::SCTP => write!(f, "SCTP"),
+            Protocol::Raw => write!(f, "RAW"),
+            Protocol::ICMP => write!(f, "ICMP"),
+            Protocol::Other(name) => write!(f, "{}", name),
         }
     }
 }
 
-impl From<&str> for Protocol {
-    fn from(s: &str) -> Self {
+/// Socket type of a Unix domain socket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnixSocketType {
+    Stream,
+    Datagram,
+    SeqPacket,
+}
+
+impl std::fmt::Display for UnixSocketType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnixSocketType::Stream => write!(f, "STREAM"),
+            UnixSocketType::Datagram => write!(f, "DGRAM"),
+            UnixSocketType::SeqPacket => write!(f, "SEQPACKET"),
+        }
+    }
+}
+
+/// Error returned when a protocol name is not recognised
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownProtocol(pub String);
+
+impl std::fmt::Display for UnknownProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown protocol: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownProtocol {}
+
+impl std::str::FromStr for Protocol {
+    type Err = UnknownProtocol;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_uppercase().as_str() {
-            "TCP" => Protocol::TCP,
-            "UDP" => Protocol::UDP,
-            _ => Protocol::TCP, // Default fallback
+            "TCP" => Ok(Protocol::TCP),
+            "UDP" => Ok(Protocol::UDP),
+            // Names the whole family; compare with `same_kind` to match every socket type
+            "UNIX" => Ok(Protocol::Unix(UnixSocketType::Stream)),
+            "SCTP" => Ok(Protocol::SCTP),
+            "RAW" => Ok(Protocol::Raw),
+            "ICMP" => Ok(Protocol::ICMP),
+            _ => Err(UnknownProtocol(s.to_string())),
+        }
+    }
+}
+
+impl Protocol {
+    /// Whether two protocols are the same kind: every Unix socket type
+    /// (stream, dgram, seqpacket) counts as one family
+    pub fn same_kind(&self, other: &Protocol) -> bool {
+        match (self, other) {
+            (Protocol::Unix(_), Protocol::Unix(_)) => true,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
         }
     }
 }
@@ -44,6 +108,13 @@ pub enum ConnectionState {
     Unknown(u32),
 }
 
+impl ConnectionState {
+    /// TCP listeners and bound UDP sockets (Windows reports the former as `Listen`)
+    pub fn is_listening(&self) -> bool {
+        matches!(self, ConnectionState::Listen | ConnectionState::Listening)
+    }
+}
+
 impl std::fmt::Display for ConnectionState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -85,6 +156,179 @@ impl From<u32> for ConnectionState {
     }
 }
 
+/// Kernel timer pending on a socket (`tr` column of `/proc/net/tcp`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SocketTimer {
+    Retransmit,
+    Keepalive,
+    TimeWait,
+    ZeroWindowProbe,
+    Unknown(u8),
+}
+
+impl From<u8> for SocketTimer {
+    fn from(code: u8) -> Self {
+        match code {
+            1 => SocketTimer::Retransmit,
+            2 => SocketTimer::Keepalive,
+            3 => SocketTimer::TimeWait,
+            4 => SocketTimer::ZeroWindowProbe,
+            _ => SocketTimer::Unknown(code),
+        }
+    }
+}
+
+/// Socket-level detail beyond the 4-tuple
+///
+/// Every field is optional: only some backends (currently Linux `/proc/net`)
+/// can report them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SocketDetails {
+    pub inode: Option<u64>,
+    /// Inode of the connected peer (Unix domain sockets)
+    pub peer_inode: Option<u64>,
+    pub uid: Option<u32>,
+    /// Bytes queued for the application to read
+    pub rx_queue: Option<u64>,
+    /// Bytes queued for transmission (unacknowledged for TCP)
+    pub tx_queue: Option<u64>,
+    pub retransmits: Option<u32>,
+    pub timer: Option<SocketTimer>,
+    /// Milliseconds until the pending timer fires
+    pub timer_expires_ms: Option<u64>,
+    /// Smoothed TCP round-trip time, in microseconds (Linux `tcp_info`)
+    pub rtt_us: Option<u32>,
+    /// TCP congestion window, in segments
+    pub cwnd: Option<u32>,
+    /// Bytes sent and acknowledged by the peer over the connection's lifetime
+    pub bytes_sent: Option<u64>,
+    pub bytes_received: Option<u64>,
+    /// Throughput since the previous monitor tick, in bytes per second
+    pub send_rate: Option<f64>,
+    pub recv_rate: Option<f64>,
+}
+
+/// Where the owning process runs: executable, network namespace, container and cgroup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessContext {
+    /// Inode of the network namespace (`/proc/<pid>/ns/net`)
+    pub net_namespace: Option<u64>,
+    /// Whether that namespace differs from the one the app runs in
+    pub foreign_namespace: bool,
+    /// Full container ID when the cgroup path belongs to a container runtime
+    pub container_id: Option<String>,
+    /// docker, podman, containerd, crio, kubernetes or lxc
+    pub container_runtime: Option<String>,
+    /// Unified-hierarchy cgroup path, e.g. `/system.slice/nginx.service`
+    pub cgroup: Option<String>,
+    /// Owning systemd unit: the cgroup's service, or the `.socket` unit for pid 1 listeners
+    pub systemd_unit: Option<String>,
+    /// Executable path (`/proc/<pid>/exe`); ends in ` (deleted)` when the binary was removed
+    pub exe: Option<String>,
+}
+
+/// Where a forwarding listener (docker-proxy, ssh -L, ...) sends its traffic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForwardTarget {
+    /// Forwarding program: docker-proxy, kubectl, ssh or socat
+    pub forwarder: String,
+    /// Destination host, container IP, Unix socket path or Kubernetes resource
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// What is known about a remote address from its range and the local ASN/GeoIP databases
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    /// loopback, private, shared, link_local, multicast or public
+    pub scope: String,
+    pub asn: Option<u32>,
+    /// Organisation owning the AS, e.g. `GOOGLE`
+    pub org: Option<String>,
+    /// ISO 3166 country code
+    pub country: Option<String>,
+}
+
+/// What kind of link a network interface is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterfaceKind {
+    Loopback,
+    Ethernet,
+    Wifi,
+    /// tun/tap, WireGuard, PPP and other point-to-point links (usually a VPN)
+    Tunnel,
+    /// Docker's `docker0` and `br-<network>` bridges
+    DockerBridge,
+    Bridge,
+    /// Container end of a veth pair
+    Veth,
+    Other,
+}
+
+impl InterfaceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InterfaceKind::Loopback => "loopback",
+            InterfaceKind::Ethernet => "ethernet",
+            InterfaceKind::Wifi => "wifi",
+            InterfaceKind::Tunnel => "tunnel",
+            InterfaceKind::DockerBridge => "docker_bridge",
+            InterfaceKind::Bridge => "bridge",
+            InterfaceKind::Veth => "veth",
+            InterfaceKind::Other => "other",
+        }
+    }
+}
+
+/// The network interface a local address belongs to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceInfo {
+    pub name: String,
+    pub kind: InterfaceKind,
+    /// Lowercase IFF_* names that are set: up, running, loopback, pointopoint, ...
+    pub flags: Vec<String>,
+}
+
+/// Which suspicious-connection heuristic matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The process's executable was deleted after it started
+    DeletedExecutable,
+    /// The executable lives in /tmp, /var/tmp or /dev/shm
+    TempExecutable,
+    /// A shell or script interpreter listens on an unprivileged port
+    InterpreterListener,
+    /// Listening on or connecting to a port associated with malware
+    SuspiciousPort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// Something odd about a connection, worth a second look rather than proof of compromise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// A user-defined label attached by a matching label rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionLabel {
+    pub label: String,
+    /// CSS colour, e.g. `#22c55e`
+    pub color: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ConnectionInfo {
     pub protocol: Protocol,
@@ -95,6 +339,21 @@ pub struct ConnectionInfo {
     pub state: ConnectionState,
     pub pid: u32,
     pub process_name: String,
+    pub socket: SocketDetails,
+    pub context: ProcessContext,
+    pub forward: Option<ForwardTarget>,
+    /// Well-known service on the row's server-side port, e.g. `postgresql`
+    pub service_name: Option<String>,
+    /// PTR name of the remote address, filled asynchronously when reverse DNS is on
+    pub remote_hostname: Option<String>,
+    /// Scope of the remote address, plus ASN and country from the configured databases
+    pub remote_peer: Option<PeerInfo>,
+    /// Labels from the user's rules, in rule order
+    pub labels: Vec<ConnectionLabel>,
+    /// Interface owning the local address; `None` for wildcard binds and Unix sockets
+    pub interface: Option<InterfaceInfo>,
+    /// Matches from the suspicious-connection heuristics
+    pub findings: Vec<Finding>,
 }
 
 impl ConnectionInfo {
@@ -117,6 +376,20 @@ impl ConnectionInfo {
             state,
             pid,
             process_name,
+            socket: SocketDetails::default(),
+            context: ProcessContext::default(),
+            forward: None,
+            service_name: None,
+            remote_hostname: None,
+            remote_peer: None,
+            labels: Vec::new(),
+            interface: None,
+            findings: Vec::new(),
         }
     }
+
+    pub fn with_socket_details(mut self, socket: SocketDetails) -> Self {
+        self.socket = socket;
+        self
+    }
 }
@@ -0,0 +1,433 @@
+use crate::models::Protocol;
+use crate::monitor::{ConnectionEvent, ConnectionRecord, EventKind, MonitorTick, SnapshotObserver};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Directory for the segments inside the app data dir
+pub const HISTORY_DIR: &str = "history";
+
+pub const DEFAULT_RETENTION_DAYS: u32 = 7;
+/// Oldest segments are dropped beyond this, whatever their age
+pub const DEFAULT_MAX_BYTES: u64 = 256 * 1024 * 1024;
+/// A full snapshot is written at least this often so point-in-time
+/// queries only replay a few minutes of events
+const CHECKPOINT_MINUTES: i64 = 15;
+const DEFAULT_QUERY_LIMIT: usize = 5000;
+const DEFAULT_QUERY_WINDOW_HOURS: i64 = 1;
+
+/// A diff event with the tick it was seen on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: ConnectionEvent,
+}
+
+/// One line of a segment file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum HistoryLine {
+    /// Every connection open at `at`
+    Checkpoint {
+        at: DateTime<Utc>,
+        connections: Vec<ConnectionRecord>,
+    },
+    Event(HistoryEvent),
+}
+
+impl HistoryLine {
+    fn at(&self) -> DateTime<Utc> {
+        match self {
+            HistoryLine::Checkpoint { at, .. } => *at,
+            HistoryLine::Event(event) => event.at,
+        }
+    }
+}
+
+/// Filters for history queries; every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Defaults to one hour before `to`
+    pub from: Option<DateTime<Utc>>,
+    /// Defaults to now
+    pub to: Option<DateTime<Utc>>,
+    /// Local or remote port
+    pub port: Option<u16>,
+    /// Case-insensitive substring of the process name
+    pub process: Option<String>,
+    /// Most recent events returned; defaults to 5000
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, record: &ConnectionRecord) -> bool {
+        self.port.is_none_or(|port| record.local_port == port || record.remote_port == port)
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|process| record.process_name.to_lowercase().contains(&process.to_lowercase()))
+    }
+}
+
+/// Errors from the history store
+#[derive(Debug, Clone)]
+pub enum HistoryError {
+    /// No app data dir, so nothing was ever recorded
+    NotAvailable,
+    Invalid(String),
+    Io(String),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::NotAvailable => write!(f, "Connection history is not available"),
+            HistoryError::Invalid(msg) => write!(f, "Invalid history query: {}", msg),
+            HistoryError::Io(msg) => write!(f, "History I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+struct HistoryConfig {
+    enabled: bool,
+    retention_days: u32,
+    max_bytes: u64,
+    monitor_interval_secs: u32,
+}
+
+#[derive(Default)]
+struct WriterState {
+    day: Option<NaiveDate>,
+    last_checkpoint: Option<DateTime<Utc>>,
+}
+
+/// Append-only log of monitor diff events, one JSON Lines file per UTC day
+///
+/// Each segment starts with a checkpoint (the full connection list) and gets
+/// another one every 15 minutes, so "what was open at 14:05" replays events
+/// from the nearest earlier checkpoint instead of from midnight. Retention
+/// deletes whole segments, by age and then by total size. A torn last line
+/// after a crash is skipped when reading.
+pub struct HistoryStore {
+    dir: Mutex<Option<PathBuf>>,
+    config: Mutex<HistoryConfig>,
+    writer: Mutex<WriterState>,
+}
+
+impl HistoryStore {
+    pub fn new() -> Self {
+        Self {
+            dir: Mutex::new(None),
+            config: Mutex::new(HistoryConfig {
+                enabled: true,
+                retention_days: DEFAULT_RETENTION_DAYS,
+                max_bytes: DEFAULT_MAX_BYTES,
+                monitor_interval_secs: 10,
+            }),
+            writer: Mutex::new(WriterState::default()),
+        }
+    }
+
+    /// Record into `dir` from now on, dropping segments past retention
+    pub fn set_dir(&self, dir: PathBuf) {
+        self.prune(&dir, Utc::now().date_naive());
+        *self.dir.lock().unwrap() = Some(dir);
+    }
+
+    pub fn configure(&self, enabled: bool, retention_days: u32, monitor_interval_secs: u32) {
+        let mut config = self.config.lock().unwrap();
+        config.enabled = enabled;
+        config.retention_days = retention_days;
+        config.monitor_interval_secs = monitor_interval_secs;
+    }
+
+    /// Longest silence between two lines while recording
+    ///
+    /// Quiet ticks write nothing, but a checkpoint is due every 15 minutes,
+    /// so a longer gap means the app was closed or history was off.
+    fn max_gap(&self) -> ChronoDuration {
+        let interval = ChronoDuration::seconds(self.config.lock().unwrap().monitor_interval_secs as i64);
+        ChronoDuration::minutes(CHECKPOINT_MINUTES).max(interval) + interval
+    }
+
+    /// Diff events in the query window, oldest first
+    pub fn events(&self, query: &HistoryQuery) -> Result<Vec<HistoryEvent>, HistoryError> {
+        let dir = self.dir()?;
+        let to = query.to.unwrap_or_else(Utc::now);
+        let from = query.from.unwrap_or(to - ChronoDuration::hours(DEFAULT_QUERY_WINDOW_HOURS));
+        if from > to {
+            return Err(HistoryError::Invalid("'from' is after 'to'".to_string()));
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+        let mut events = VecDeque::new();
+        let mut day = from.date_naive();
+        while day <= to.date_naive() {
+            for line in read_segment(&segment_path(&dir, day))? {
+                if let HistoryLine::Event(event) = line {
+                    if event.at >= from && event.at <= to && query.matches(&event.event.connection) {
+                        events.push_back(event);
+                        // Keep the most recent ones when the window holds too many
+                        if events.len() > limit {
+                            events.pop_front();
+                        }
+                    }
+                }
+            }
+            day = day.succ_opt().unwrap_or(NaiveDate::MAX);
+        }
+        Ok(events.into())
+    }
+
+    /// Connections open at `at`, replayed from the nearest earlier checkpoint that day
+    ///
+    /// Empty when nothing was being recorded at that time: a gap longer than
+    /// `max_gap` drops the replayed state until the next checkpoint.
+    pub fn connections_at(&self, at: DateTime<Utc>, query: &HistoryQuery) -> Result<Vec<ConnectionRecord>, HistoryError> {
+        let dir = self.dir()?;
+        let max_gap = self.max_gap();
+        let mut open: Option<HashMap<_, ConnectionRecord>> = None;
+        let mut last_seen: Option<DateTime<Utc>> = None;
+
+        for line in read_segment(&segment_path(&dir, at.date_naive()))? {
+            if line.at() > at {
+                break;
+            }
+            if last_seen.is_some_and(|last| line.at() - last > max_gap) {
+                open = None;
+            }
+            last_seen = Some(line.at());
+            match line {
+                HistoryLine::Checkpoint { connections, .. } => {
+                    open = Some(connections.into_iter().map(|record| (record.key(), record)).collect());
+                }
+                HistoryLine::Event(event) => {
+                    let Some(open) = open.as_mut() else {
+                        continue;
+                    };
+                    let record = event.event.connection;
+                    match event.event.kind {
+                        EventKind::Opened | EventKind::StateChanged => {
+                            open.insert(record.key(), record);
+                        }
+                        EventKind::Closed => {
+                            open.remove(&record.key());
+                        }
+                    }
+                }
+            }
+        }
+
+        if last_seen.is_none_or(|last| at - last > max_gap) {
+            open = None;
+        }
+        let mut connections: Vec<ConnectionRecord> = open
+            .unwrap_or_default()
+            .into_values()
+            .filter(|record| query.matches(record))
+            .collect();
+        connections.sort_by(|a, b| (a.local_port, &a.remote_address).cmp(&(b.local_port, &b.remote_address)));
+        Ok(connections)
+    }
+
+    fn dir(&self) -> Result<PathBuf, HistoryError> {
+        self.dir.lock().unwrap().clone().ok_or(HistoryError::NotAvailable)
+    }
+
+    fn record(&self, dir: &Path, tick: &MonitorTick) -> std::io::Result<()> {
+        let day = tick.at.date_naive();
+        let mut writer = self.writer.lock().unwrap();
+        let new_day = writer.day != Some(day);
+        let checkpoint_due = new_day
+            || tick.first
+            || writer
+                .last_checkpoint
+                .is_none_or(|last| tick.at - last >= ChronoDuration::minutes(CHECKPOINT_MINUTES));
+        if !checkpoint_due && tick.events.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new().create(true).append(true).open(segment_path(dir, day))?;
+        let mut out = BufWriter::new(file);
+        for event in tick.events {
+            let line = HistoryLine::Event(HistoryEvent {
+                at: tick.at,
+                event: event.clone(),
+            });
+            writeln!(out, "{}", serde_json::to_string(&line)?)?;
+        }
+        if checkpoint_due {
+            let connections = tick
+                .connections
+                .iter()
+                .filter(|conn| !matches!(conn.protocol, Protocol::Unix(_)))
+                .map(ConnectionRecord::from)
+                .collect();
+            let line = HistoryLine::Checkpoint { at: tick.at, connections };
+            writeln!(out, "{}", serde_json::to_string(&line)?)?;
+            writer.last_checkpoint = Some(tick.at);
+        }
+        out.flush()?;
+
+        if new_day {
+            writer.day = Some(day);
+            drop(writer);
+            self.prune(dir, day);
+        }
+        Ok(())
+    }
+
+    /// Delete segments older than the retention period, then the oldest until under the size cap
+    fn prune(&self, dir: &Path, today: NaiveDate) {
+        let (retention_days, max_bytes) = {
+            let config = self.config.lock().unwrap();
+            (config.retention_days, config.max_bytes)
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut segments: Vec<(NaiveDate, PathBuf, u64)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let day = segment_day(&entry.file_name().to_string_lossy())?;
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                Some((day, entry.path(), size))
+            })
+            .collect();
+        segments.sort_by_key(|(day, _, _)| *day);
+
+        let oldest_kept = today - ChronoDuration::days(retention_days as i64);
+        let mut total: u64 = segments.iter().map(|(_, _, size)| size).sum();
+        for (day, path, size) in &segments {
+            let expired = *day < oldest_kept;
+            // Never drop today's segment for size; it is being written
+            let oversized = total > max_bytes && *day < today;
+            if !expired && !oversized {
+                continue;
+            }
+            match fs::remove_file(path) {
+                Ok(()) => total -= size,
+                Err(e) => eprintln!("History: failed to remove {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+impl SnapshotObserver for HistoryStore {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn observe(&self, tick: &MonitorTick) {
+        if !self.config.lock().unwrap().enabled {
+            // Start over with a checkpoint once recording resumes
+            self.writer.lock().unwrap().last_checkpoint = None;
+            return;
+        }
+        let Ok(dir) = self.dir() else {
+            return;
+        };
+        if let Err(e) = self.record(&dir, tick) {
+            eprintln!("History: failed to record tick: {}", e);
+        }
+    }
+}
+
+fn segment_path(dir: &Path, day: NaiveDate) -> PathBuf {
+    dir.join(format!("events-{}.jsonl", day.format("%Y-%m-%d")))
+}
+
+fn segment_day(file_name: &str) -> Option<NaiveDate> {
+    let day = file_name.strip_prefix("events-")?.strip_suffix(".jsonl")?;
+    NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()
+}
+
+/// Parseable lines of a segment, read as they are consumed; a missing segment is simply empty
+fn read_segment(path: &Path) -> Result<impl Iterator<Item = HistoryLine>, HistoryError> {
+    let file = match fs::File::open(path) {
+        Ok(file) => Some(file),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(HistoryError::Io(format!("{}: {}", path.display(), e))),
+    };
+    Ok(file
+        .into_iter()
+        .flat_map(|file| BufReader::new(file).lines().map_while(Result::ok))
+        .filter_map(|line| serde_json::from_str(&line).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionInfo, ConnectionState};
+    use crate::monitor::Monitor;
+
+    #[test]
+    fn test_record_and_query() {
+        let dir = std::env::temp_dir().join(format!("portviewer-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let monitor = Monitor::new();
+        monitor.history().set_dir(dir.clone());
+
+        let db = |port: u16, process: &str| {
+            ConnectionInfo::new(Protocol::TCP, "10.0.0.1".to_string(), port, "10.0.0.5".to_string(), 5432, ConnectionState::Established, 7, process.to_string())
+        };
+        let t0: DateTime<Utc> = "2026-03-01T14:00:00Z".parse().unwrap();
+        let minute = ChronoDuration::minutes(1);
+
+        monitor.tick(t0, &[db(40000, "api")]);
+        monitor.tick(t0 + minute * 5, &[db(40000, "api"), db(40001, "worker")]);
+        monitor.tick(t0 + minute * 10, &[db(40001, "worker")]);
+
+        let history = monitor.history();
+        let at = |minutes: i64| history.connections_at(t0 + minute * minutes as i32, &HistoryQuery::default()).unwrap();
+        assert_eq!(at(1).len(), 1);
+        assert_eq!(at(6).len(), 2);
+        assert_eq!(at(11).len(), 1);
+        assert_eq!(at(11)[0].process_name, "worker");
+        // Nothing was recorded after the last tick, e.g. the app was closed
+        assert!(at(60).is_empty());
+
+        let query = HistoryQuery {
+            from: Some(t0),
+            to: Some(t0 + minute * 60),
+            port: Some(5432),
+            process: Some("API".to_string()),
+            ..HistoryQuery::default()
+        };
+        let events = history.events(&query).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event.kind, EventKind::Closed);
+        assert_eq!(events[0].at, t0 + minute * 10);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_by_age() {
+        let dir = std::env::temp_dir().join(format!("portviewer-prune-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let today: NaiveDate = "2026-03-20".parse().unwrap();
+        for day in ["2026-03-01", "2026-03-19", "2026-03-20"] {
+            fs::write(dir.join(format!("events-{}.jsonl", day)), "{}\n").unwrap();
+        }
+        fs::write(dir.join("unrelated.txt"), "keep").unwrap();
+
+        let store = HistoryStore::new();
+        store.prune(&dir, today);
+        let mut left: Vec<String> = fs::read_dir(&dir).unwrap().flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect();
+        left.sort();
+        assert_eq!(left, vec!["events-2026-03-19.jsonl", "events-2026-03-20.jsonl", "unrelated.txt"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+use crate::models::{ConnectionInfo, Protocol};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub mod history;
+pub mod rules;
+pub mod throughput;
+pub mod trends;
+
+/// Everything except the state, which is what changes over a connection's life
+type ConnectionKey = (String, String, u16, String, u16, u32);
+type Snapshot = HashMap<ConnectionKey, ConnectionRecord>;
+
+/// Compact, serializable copy of the fields that identify and describe a connection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionRecord {
+    pub protocol: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub state: String,
+    pub pid: u32,
+    pub process_name: String,
+}
+
+impl ConnectionRecord {
+    fn key(&self) -> ConnectionKey {
+        (
+            self.protocol.clone(),
+            self.local_address.clone(),
+            self.local_port,
+            self.remote_address.clone(),
+            self.remote_port,
+            self.pid,
+        )
+    }
+}
+
+impl From<&ConnectionInfo> for ConnectionRecord {
+    fn from(conn: &ConnectionInfo) -> Self {
+        Self {
+            protocol: conn.protocol.to_string(),
+            local_address: conn.local_address.clone(),
+            local_port: conn.local_port,
+            remote_address: conn.remote_address.clone(),
+            remote_port: conn.remote_port,
+            state: conn.state.to_string(),
+            pid: conn.pid,
+            process_name: conn.process_name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Opened,
+    Closed,
+    StateChanged,
+}
+
+/// One difference between two consecutive snapshots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionEvent {
+    pub kind: EventKind,
+    /// The connection as seen after the change (before it, for `Closed`)
+    pub connection: ConnectionRecord,
+    /// Only for `StateChanged`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_state: Option<String>,
+}
+
+/// What observers see on every monitor tick
+pub struct MonitorTick<'a> {
+    pub at: DateTime<Utc>,
+    pub connections: &'a [ConnectionInfo],
+    /// Differences from the previous tick; empty on the first one
+    pub events: &'a [ConnectionEvent],
+    /// True for the first tick after start, when there is nothing to diff against
+    pub first: bool,
+}
+
+/// Something that consumes the stream of monitor ticks (history, trends, alerts)
+///
+/// Observers run on the blocking pool one after another, like enrichers, and
+/// must handle their own failures; a broken observer never stops the monitor.
+pub trait SnapshotObserver: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn observe(&self, tick: &MonitorTick);
+}
+
+/// Observers slower than this are reported so regressions are noticed
+const SLOW_OBSERVER_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Background snapshot loop state: diffs consecutive snapshots and fans them out
+///
+/// The loop itself lives in `main.rs` so it can read the interval from the
+/// settings on every tick; `tick` is the testable part. Unix sockets are
+/// left out of the diff because they churn constantly and carry no ports.
+pub struct Monitor {
+    observers: Vec<Arc<dyn SnapshotObserver>>,
+    history: Arc<history::HistoryStore>,
+    trends: Arc<trends::TrendTracker>,
+    rules: Arc<rules::RuleEngine>,
+    throughput: Arc<throughput::ThroughputTracker>,
+    previous: Mutex<Option<Snapshot>>,
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        let history = Arc::new(history::HistoryStore::new());
+        let trends = Arc::new(trends::TrendTracker::new());
+        let rules = Arc::new(rules::RuleEngine::new());
+        let throughput = Arc::new(throughput::ThroughputTracker::new());
+        let observers: Vec<Arc<dyn SnapshotObserver>> =
+            vec![history.clone(), trends.clone(), rules.clone(), throughput.clone()];
+        Self {
+            observers,
+            history,
+            trends,
+            rules,
+            throughput,
+            previous: Mutex::new(None),
+        }
+    }
+
+    /// Recorded diff events, idle until pointed at a directory
+    pub fn history(&self) -> &history::HistoryStore {
+        &self.history
+    }
+
+    /// Per-process socket counts and the leaks they suggest
+    pub fn trends(&self) -> &trends::TrendTracker {
+        &self.trends
+    }
+
+    /// User alert rules and the alerts they raise
+    pub fn rules(&self) -> &rules::RuleEngine {
+        &self.rules
+    }
+
+    /// Per-connection byte rates between the last two ticks (Linux `tcp_info`)
+    pub fn throughput(&self) -> &throughput::ThroughputTracker {
+        &self.throughput
+    }
+
+    /// Diff `connections` against the previous tick and hand both to every observer
+    pub fn tick(&self, at: DateTime<Utc>, connections: &[ConnectionInfo]) -> Vec<ConnectionEvent> {
+        let current: Snapshot = connections
+            .iter()
+            .filter(|conn| !matches!(conn.protocol, Protocol::Unix(_)))
+            .map(|conn| {
+                let record = ConnectionRecord::from(conn);
+                (record.key(), record)
+            })
+            .collect();
+
+        let (events, first) = {
+            let mut previous = self.previous.lock().unwrap();
+            let events = match previous.as_ref() {
+                Some(previous) => diff(previous, &current),
+                None => Vec::new(),
+            };
+            let first = previous.is_none();
+            *previous = Some(current);
+            (events, first)
+        };
+
+        let tick = MonitorTick {
+            at,
+            connections,
+            events: &events,
+            first,
+        };
+        for observer in &self.observers {
+            let started = Instant::now();
+            observer.observe(&tick);
+            if started.elapsed() > SLOW_OBSERVER_THRESHOLD {
+                println!("Observer '{}' took {}ms", observer.name(), started.elapsed().as_millis());
+            }
+        }
+        events
+    }
+}
+
+fn diff(previous: &Snapshot, current: &Snapshot) -> Vec<ConnectionEvent> {
+    let mut events = Vec::new();
+    for (key, record) in current {
+        match previous.get(key) {
+            None => events.push(ConnectionEvent {
+                kind: EventKind::Opened,
+                connection: record.clone(),
+                previous_state: None,
+            }),
+            Some(before) if before.state != record.state => events.push(ConnectionEvent {
+                kind: EventKind::StateChanged,
+                connection: record.clone(),
+                previous_state: Some(before.state.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, record) in previous {
+        if !current.contains_key(key) {
+            events.push(ConnectionEvent {
+                kind: EventKind::Closed,
+                connection: record.clone(),
+                previous_state: None,
+            });
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    #[test]
+    fn test_tick_diffs_consecutive_snapshots() {
+        let conn = |port: u16, state: ConnectionState| {
+            ConnectionInfo::new(Protocol::TCP, "10.0.0.1".to_string(), port, "10.0.0.2".to_string(), 5432, state, 7, "app".to_string())
+        };
+        let monitor = Monitor::new();
+        let now = Utc::now();
+
+        assert!(monitor.tick(now, &[conn(40000, ConnectionState::Established)]).is_empty());
+
+        let events = monitor.tick(now, &[conn(40000, ConnectionState::CloseWait), conn(40001, ConnectionState::Established)]);
+        assert_eq!(events.len(), 2);
+        let changed = events.iter().find(|e| e.kind == EventKind::StateChanged).unwrap();
+        assert_eq!(changed.previous_state.as_deref(), Some("ESTABLISHED"));
+        assert_eq!(changed.connection.state, "CLOSE_WAIT");
+
+        let events = monitor.tick(now, &[conn(40001, ConnectionState::Established)]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::Closed);
+        assert_eq!(events[0].connection.local_port, 40000);
+    }
+}
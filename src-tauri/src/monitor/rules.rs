@@ -0,0 +1,509 @@
+use crate::enrich::labels::{cidr_contains, glob_match, parse_cidr};
+use crate::models::{ConnectionInfo, Protocol};
+use crate::monitor::{MonitorTick, SnapshotObserver};
+use crate::ports::{addresses_overlap, parse_bind_address};
+use crate::fs_util::{JsonStore, StoreError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// File name of the rule store inside the app data dir
+pub const ALERT_RULES_FILE: &str = "alert_rules.json";
+
+/// Bumped whenever the on-disk layout changes incompatibly
+const ALERT_RULES_FILE_VERSION: u32 = 1;
+
+/// Fired and resolved alerts kept for the UI
+const ALERT_LOG_CAPACITY: usize = 200;
+
+/// Transports a `must_listen` rule can watch; others have no listening ports
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenProtocol {
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+impl ListenProtocol {
+    fn protocol(self) -> Protocol {
+        match self {
+            ListenProtocol::Tcp => Protocol::TCP,
+            ListenProtocol::Udp => Protocol::UDP,
+            ListenProtocol::Sctp => Protocol::SCTP,
+        }
+    }
+}
+
+/// What must (or must not) be true of every snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// Something listens on `port`; a wildcard listener satisfies a specific `address`
+    MustListen {
+        port: u16,
+        /// `tcp` (default), `udp` or `sctp`
+        #[serde(default)]
+        protocol: Option<ListenProtocol>,
+        #[serde(default)]
+        address: Option<String>,
+    },
+    /// Nothing listens on 0.0.0.0 or :: unless its port or process is allowed
+    NoWildcardListener {
+        #[serde(default)]
+        allow_ports: Vec<u16>,
+        /// Process names, case-insensitive, with `*` and `?` wildcards
+        #[serde(default)]
+        allow_processes: Vec<String>,
+    },
+    /// Processes matching `process` never connect to `remote`
+    MustNotConnect {
+        /// Process name, case-insensitive, with `*` and `?` wildcards
+        process: String,
+        /// Address, CIDR block, or host name as reverse DNS resolves it
+        remote: String,
+        #[serde(default)]
+        port: Option<u16>,
+    },
+}
+
+/// A user rule, evaluated against every monitor snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Assigned on creation; ignored when creating a rule
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub condition: RuleCondition,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl AlertRule {
+    pub fn validate(&self) -> Result<(), AlertError> {
+        if self.name.trim().is_empty() {
+            return Err(AlertError::Invalid("name must not be empty".to_string()));
+        }
+        match &self.condition {
+            RuleCondition::MustListen { port, address, .. } => {
+                if *port == 0 {
+                    return Err(AlertError::Invalid("port must be 1-65535".to_string()));
+                }
+                if let Some(address) = address {
+                    if parse_bind_address(address).is_none() {
+                        return Err(AlertError::Invalid(format!("'{}' is not an IP address", address)));
+                    }
+                }
+                Ok(())
+            }
+            RuleCondition::NoWildcardListener { .. } => Ok(()),
+            RuleCondition::MustNotConnect { process, remote, .. } => {
+                if process.trim().is_empty() || remote.trim().is_empty() {
+                    return Err(AlertError::Invalid("process and remote must not be empty".to_string()));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Every way the snapshot breaks this rule, keyed so a violation that
+    /// persists across ticks is recognised as the same one
+    fn violations(&self, connections: &[ConnectionInfo]) -> Vec<(String, String)> {
+        // Sockets inside container namespaces are not reachable the way host ones are
+        let host = connections.iter().filter(|conn| !conn.context.foreign_namespace);
+        match &self.condition {
+            RuleCondition::MustListen { port, protocol, address } => {
+                let protocol = protocol.unwrap_or(ListenProtocol::Tcp).protocol();
+                let address = address.as_deref().and_then(parse_bind_address);
+                let listening = host.into_iter().any(|conn| {
+                    conn.local_port == *port
+                        && conn.protocol.same_kind(&protocol)
+                        && conn.state.is_listening()
+                        && address.is_none_or(|address| {
+                            parse_bind_address(&conn.local_address).is_some_and(|local| addresses_overlap(local, address))
+                        })
+                });
+                if listening {
+                    Vec::new()
+                } else {
+                    vec![("missing".to_string(), format!("Nothing is listening on {} port {}", protocol, port))]
+                }
+            }
+            RuleCondition::NoWildcardListener { allow_ports, allow_processes } => {
+                let allow_processes: Vec<String> = allow_processes.iter().map(|p| p.to_lowercase()).collect();
+                let mut found: Vec<(String, String)> = host
+                    .filter(|conn| conn.state.is_listening())
+                    .filter(|conn| parse_bind_address(&conn.local_address).is_some_and(|ip| ip.is_unspecified()))
+                    .filter(|conn| !allow_ports.contains(&conn.local_port))
+                    .filter(|conn| {
+                        let name = conn.process_name.to_lowercase();
+                        !allow_processes.iter().any(|pattern| glob_match(pattern, &name))
+                    })
+                    .map(|conn| {
+                        (
+                            format!("{}/{}/{}", conn.protocol, conn.local_port, conn.process_name),
+                            format!(
+                                "{} ({}) listens on {}:{} ({})",
+                                conn.process_name, conn.pid, conn.local_address, conn.local_port, conn.protocol
+                            ),
+                        )
+                    })
+                    .collect();
+                found.sort();
+                found.dedup_by(|a, b| a.0 == b.0);
+                found
+            }
+            RuleCondition::MustNotConnect { process, remote, port } => {
+                let pattern = process.to_lowercase();
+                let network = parse_cidr(remote);
+                let mut found: Vec<(String, String)> = host
+                    .filter(|conn| conn.remote_port != 0 && !conn.state.is_listening())
+                    .filter(|conn| port.is_none_or(|port| conn.remote_port == port))
+                    .filter(|conn| glob_match(&pattern, &conn.process_name.to_lowercase()))
+                    .filter(|conn| match network {
+                        Some((network, prefix)) => conn
+                            .remote_address
+                            .parse::<IpAddr>()
+                            .is_ok_and(|ip| cidr_contains(network, prefix, ip)),
+                        None => conn
+                            .remote_hostname
+                            .as_ref()
+                            .is_some_and(|host| host.eq_ignore_ascii_case(remote.trim())),
+                    })
+                    .map(|conn| {
+                        (
+                            format!("{}/{}:{}", conn.pid, conn.remote_address, conn.remote_port),
+                            format!(
+                                "{} ({}) is connected to {}:{}",
+                                conn.process_name, conn.pid, conn.remote_address, conn.remote_port
+                            ),
+                        )
+                    })
+                    .collect();
+                found.sort();
+                found.dedup_by(|a, b| a.0 == b.0);
+                found
+            }
+        }
+    }
+}
+
+/// A rule starting or ceasing to be violated
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertEvent {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub at: DateTime<Utc>,
+    pub message: String,
+    /// False when the violation starts, true when it goes away
+    pub resolved: bool,
+}
+
+/// Errors from validating or persisting alert rules
+#[derive(Debug, Clone)]
+pub enum AlertError {
+    /// Rule failed validation
+    Invalid(String),
+    /// No rule with this id
+    NotFound(String),
+    /// Reading or writing the rule file failed
+    Io(String),
+}
+
+impl fmt::Display for AlertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertError::Invalid(msg) => write!(f, "Invalid alert rule: {}", msg),
+            AlertError::NotFound(id) => write!(f, "No alert rule with id {}", id),
+            AlertError::Io(msg) => write!(f, "Alert rule store I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AlertError {}
+
+impl From<StoreError> for AlertError {
+    fn from(e: StoreError) -> Self {
+        AlertError::Io(e.0)
+    }
+}
+
+/// Called for every fired or resolved alert, on the monitor's thread
+pub type AlertNotifier = Box<dyn Fn(&AlertEvent) + Send + Sync>;
+
+/// Alert rules, their evaluation against each snapshot, and the alerts that result
+///
+/// Alerts are edge-triggered: a violation fires once when it first appears
+/// and resolves once when it is gone, rather than on every tick. Rules are
+/// persisted the same way as label rules.
+pub struct RuleEngine {
+    rules: JsonStore<AlertRule>,
+    /// Currently violated (rule id, violation key) with the event that fired it
+    active: Mutex<HashMap<(String, String), AlertEvent>>,
+    log: Mutex<VecDeque<AlertEvent>>,
+    notifier: RwLock<Option<AlertNotifier>>,
+    /// Host names in `must_not_connect` only match when reverse DNS fills them in
+    reverse_dns: AtomicBool,
+}
+
+impl RuleEngine {
+    pub fn new() -> Self {
+        Self {
+            rules: JsonStore::new("rules", ALERT_RULES_FILE_VERSION),
+            active: Mutex::new(HashMap::new()),
+            log: Mutex::new(VecDeque::new()),
+            notifier: RwLock::new(None),
+            reverse_dns: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_reverse_dns(&self, enabled: bool) {
+        self.reverse_dns.store(enabled, Ordering::Relaxed);
+    }
+
+    /// `validate` plus checks that depend on the current settings; saved rules
+    /// only get `validate` on load so a settings change never deletes them
+    fn check(&self, rule: &AlertRule) -> Result<(), AlertError> {
+        rule.validate()?;
+        if let RuleCondition::MustNotConnect { remote, .. } = &rule.condition {
+            if parse_cidr(remote).is_none() && !self.reverse_dns.load(Ordering::Relaxed) {
+                return Err(AlertError::Invalid(format!(
+                    "'{}' is a host name, which only matches with reverse DNS on; use an address or CIDR block",
+                    remote.trim()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_notifier(&self, notifier: AlertNotifier) {
+        *self.notifier.write().unwrap() = Some(notifier);
+    }
+
+    /// Read rules from `path` and persist to it from now on; a missing file means no rules
+    pub fn load(&self, path: PathBuf) -> Result<usize, AlertError> {
+        Ok(self.rules.load(path, |rule: &AlertRule| match rule.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                println!("Alerts: skipping rule '{}': {}", rule.name, e);
+                false
+            }
+        })?)
+    }
+
+    pub fn rules(&self) -> Vec<AlertRule> {
+        self.rules.items()
+    }
+
+    /// Add a rule with a fresh id
+    pub fn create(&self, mut rule: AlertRule) -> Result<AlertRule, AlertError> {
+        self.check(&rule)?;
+        rule.id = uuid::Uuid::new_v4().to_string();
+
+        self.rules.update(|rules| {
+            rules.push(rule.clone());
+            Ok(rule)
+        })
+    }
+
+    /// Replace the rule with the same id, keeping its position
+    pub fn update(&self, rule: AlertRule) -> Result<AlertRule, AlertError> {
+        self.check(&rule)?;
+
+        self.rules.update(|rules| {
+            let existing = rules
+                .iter_mut()
+                .find(|existing| existing.id == rule.id)
+                .ok_or_else(|| AlertError::NotFound(rule.id.clone()))?;
+            *existing = rule.clone();
+            Ok(rule)
+        })
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), AlertError> {
+        self.rules.update(|rules| {
+            let before = rules.len();
+            rules.retain(|rule| rule.id != id);
+            if rules.len() == before {
+                return Err(AlertError::NotFound(id.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    /// Alerts firing right now, oldest first
+    pub fn active(&self) -> Vec<AlertEvent> {
+        let mut active: Vec<AlertEvent> = self.active.lock().unwrap().values().cloned().collect();
+        active.sort_by_key(|event| event.at);
+        active
+    }
+
+    /// Recently fired and resolved alerts, newest first
+    pub fn log(&self) -> Vec<AlertEvent> {
+        self.log.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    fn evaluate(&self, at: DateTime<Utc>, connections: &[ConnectionInfo]) -> Vec<AlertEvent> {
+        let rules = self.rules();
+        let mut active = self.active.lock().unwrap();
+        let mut events = Vec::new();
+        let mut still_active = HashMap::new();
+
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            for (key, message) in rule.violations(connections) {
+                let key = (rule.id.clone(), key);
+                let fired = match active.remove(&key) {
+                    Some(fired) => fired,
+                    None => {
+                        let fired = AlertEvent {
+                            rule_id: rule.id.clone(),
+                            rule_name: rule.name.clone(),
+                            at,
+                            message,
+                            resolved: false,
+                        };
+                        events.push(fired.clone());
+                        fired
+                    }
+                };
+                still_active.insert(key, fired);
+            }
+        }
+
+        // Whatever is left stopped being violated; deleted or disabled rules go quietly
+        for (_, fired) in active.drain() {
+            if rules.iter().any(|rule| rule.id == fired.rule_id && rule.enabled) {
+                events.push(AlertEvent {
+                    at,
+                    resolved: true,
+                    ..fired
+                });
+            }
+        }
+        *active = still_active;
+        events
+    }
+}
+
+impl SnapshotObserver for RuleEngine {
+    fn name(&self) -> &'static str {
+        "alert rules"
+    }
+
+    fn observe(&self, tick: &MonitorTick) {
+        let events = self.evaluate(tick.at, tick.connections);
+        if events.is_empty() {
+            return;
+        }
+
+        let notifier = self.notifier.read().unwrap();
+        let mut log = self.log.lock().unwrap();
+        for event in events {
+            println!(
+                "Alert {}: [{}] {}",
+                if event.resolved { "resolved" } else { "fired" },
+                event.rule_name,
+                event.message
+            );
+            if let Some(notify) = notifier.as_ref() {
+                notify(&event);
+            }
+            if log.len() == ALERT_LOG_CAPACITY {
+                log.pop_front();
+            }
+            log.push_back(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    fn socket(local: &str, port: u16, remote: &str, remote_port: u16, state: ConnectionState, process: &str) -> ConnectionInfo {
+        ConnectionInfo::new(Protocol::TCP, local.to_string(), port, remote.to_string(), remote_port, state, 100, process.to_string())
+    }
+
+    fn rule(id: &str, condition: RuleCondition) -> AlertRule {
+        AlertRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            condition,
+        }
+    }
+
+    #[test]
+    fn test_rules_fire_once_and_resolve() {
+        let engine = RuleEngine::new();
+        for rule in [
+            rule("postgres", RuleCondition::MustListen { port: 5432, protocol: None, address: Some("127.0.0.1".to_string()) }),
+            rule("public", RuleCondition::NoWildcardListener { allow_ports: vec![22], allow_processes: vec!["nginx*".to_string()] }),
+            rule("no-prod", RuleCondition::MustNotConnect { process: "worker".to_string(), remote: "10.20.0.0/16".to_string(), port: None }),
+        ] {
+            engine.create(rule).unwrap();
+        }
+        let now = Utc::now();
+
+        let bad = vec![
+            socket("0.0.0.0", 22, "0.0.0.0", 0, ConnectionState::Listening, "sshd"),
+            socket("0.0.0.0", 80, "0.0.0.0", 0, ConnectionState::Listening, "nginx"),
+            socket("::", 9000, "::", 0, ConnectionState::Listening, "debugserver"),
+            socket("10.0.0.2", 41000, "10.20.3.4", 5432, ConnectionState::Established, "worker"),
+        ];
+        let fired = engine.evaluate(now, &bad);
+        let mut names: Vec<&str> = fired.iter().map(|e| e.rule_name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["no-prod", "postgres", "public"]);
+        assert!(fired.iter().all(|e| !e.resolved));
+
+        // Still broken: nothing new fires
+        assert!(engine.evaluate(now, &bad).is_empty());
+        assert_eq!(engine.active().len(), 3);
+
+        let good = vec![
+            socket("0.0.0.0", 5432, "0.0.0.0", 0, ConnectionState::Listening, "postgres"),
+            socket("0.0.0.0", 80, "0.0.0.0", 0, ConnectionState::Listening, "nginx"),
+            socket("10.0.0.2", 41000, "10.30.3.4", 5432, ConnectionState::Established, "worker"),
+        ];
+        let resolved = engine.evaluate(now, &good);
+        // Postgres itself listens on the wildcard, which the "public" rule does not allow
+        assert_eq!(resolved.iter().filter(|e| e.resolved).count(), 3);
+        assert_eq!(resolved.iter().filter(|e| !e.resolved).count(), 1);
+        assert_eq!(engine.active()[0].message, "postgres (100) listens on 0.0.0.0:5432 (TCP)");
+    }
+
+    #[test]
+    fn test_rule_format_and_validation() {
+        let parsed: AlertRule =
+            serde_json::from_str(r#"{"name":"db up","rule":"must_listen","port":5432}"#).unwrap();
+        assert!(parsed.enabled);
+        assert!(parsed.validate().is_ok());
+        assert!(rule("x", RuleCondition::MustListen { port: 0, protocol: None, address: None }).validate().is_err());
+        assert!(rule("x", RuleCondition::MustNotConnect { process: "".to_string(), remote: "1.2.3.4".to_string(), port: None })
+            .validate()
+            .is_err());
+
+        // Only transports with listening ports
+        assert!(serde_json::from_str::<AlertRule>(r#"{"name":"x","rule":"must_listen","port":53,"protocol":"udp"}"#).is_ok());
+        assert!(serde_json::from_str::<AlertRule>(r#"{"name":"x","rule":"must_listen","port":53,"protocol":"unix"}"#).is_err());
+
+        // Host names need reverse DNS to ever match
+        let engine = RuleEngine::new();
+        let by_name = rule("x", RuleCondition::MustNotConnect { process: "curl".to_string(), remote: "example.com".to_string(), port: None });
+        assert!(engine.create(by_name.clone()).is_err());
+        engine.set_reverse_dns(true);
+        assert!(engine.create(by_name).is_ok());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::models::ConnectionInfo;
+use crate::monitor::{MonitorTick, SnapshotObserver};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+/// Byte counters of one socket at one tick
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: DateTime<Utc>,
+    sent: u64,
+    received: u64,
+}
+
+/// Send and receive rates of one socket, in bytes per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rate {
+    send: f64,
+    recv: f64,
+}
+
+/// A connection among the busiest at the last tick
+#[derive(Debug, Clone, Serialize)]
+pub struct TopTalker {
+    pub pid: u32,
+    pub process_name: String,
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub send_rate: f64,
+    pub recv_rate: f64,
+}
+
+/// Throughput totals as of the last monitor tick
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ThroughputStats {
+    /// Tick the totals were computed at; `None` until a socket with byte counters is seen
+    pub at: Option<DateTime<Utc>>,
+    pub send_rate: f64,
+    pub recv_rate: f64,
+    /// TCP connections that moved data since the tick before
+    pub active_connections: usize,
+    /// Busiest connections by combined rate
+    pub top: Vec<TopTalker>,
+}
+
+/// How many connections `ThroughputStats::top` lists
+const TOP_TALKERS: usize = 10;
+
+/// Per-socket throughput from `tcp_info` byte counters between monitor ticks
+///
+/// Sockets are keyed by inode. A socket only has a rate once it has been
+/// seen on two ticks; a counter going backwards means the inode was reused
+/// and starts over.
+pub struct ThroughputTracker {
+    samples: Mutex<HashMap<u64, Sample>>,
+    rates: RwLock<HashMap<u64, Rate>>,
+    stats: RwLock<ThroughputStats>,
+}
+
+impl ThroughputTracker {
+    pub fn new() -> Self {
+        Self {
+            samples: Mutex::new(HashMap::new()),
+            rates: RwLock::new(HashMap::new()),
+            stats: RwLock::new(ThroughputStats::default()),
+        }
+    }
+
+    pub fn stats(&self) -> ThroughputStats {
+        self.stats.read().unwrap().clone()
+    }
+
+    /// Copy the rates from the last tick onto a fresh snapshot
+    pub fn annotate(&self, connections: &mut [ConnectionInfo]) {
+        let rates = self.rates.read().unwrap();
+        for conn in connections.iter_mut() {
+            if let Some(rate) = conn.socket.inode.and_then(|inode| rates.get(&inode)) {
+                conn.socket.send_rate = Some(rate.send);
+                conn.socket.recv_rate = Some(rate.recv);
+            }
+        }
+    }
+
+    fn update(&self, at: DateTime<Utc>, connections: &[ConnectionInfo]) {
+        let mut samples = self.samples.lock().unwrap();
+        let mut next_samples = HashMap::new();
+        let mut rates = HashMap::new();
+        let mut stats = ThroughputStats::default();
+        let mut talkers = Vec::new();
+
+        for conn in connections {
+            let (Some(inode), Some(sent), Some(received)) =
+                (conn.socket.inode, conn.socket.bytes_sent, conn.socket.bytes_received)
+            else {
+                continue;
+            };
+            let sample = Sample { at, sent, received };
+            next_samples.insert(inode, sample);
+
+            let Some(previous) = samples.get(&inode) else {
+                continue;
+            };
+            let elapsed = (at - previous.at).num_milliseconds() as f64 / 1000.0;
+            if elapsed <= 0.0 || sent < previous.sent || received < previous.received {
+                continue;
+            }
+            let rate = Rate {
+                send: (sent - previous.sent) as f64 / elapsed,
+                recv: (received - previous.received) as f64 / elapsed,
+            };
+            rates.insert(inode, rate);
+            stats.send_rate += rate.send;
+            stats.recv_rate += rate.recv;
+            if rate.send > 0.0 || rate.recv > 0.0 {
+                stats.active_connections += 1;
+                talkers.push(TopTalker {
+                    pid: conn.pid,
+                    process_name: conn.process_name.clone(),
+                    local_address: conn.local_address.clone(),
+                    local_port: conn.local_port,
+                    remote_address: conn.remote_address.clone(),
+                    remote_port: conn.remote_port,
+                    send_rate: rate.send,
+                    recv_rate: rate.recv,
+                });
+            }
+        }
+
+        talkers.sort_by(|a, b| (b.send_rate + b.recv_rate).total_cmp(&(a.send_rate + a.recv_rate)));
+        talkers.truncate(TOP_TALKERS);
+        stats.top = talkers;
+        // Stays `None` where the backend has no byte counters, so the UI can hide it
+        stats.at = (!next_samples.is_empty()).then_some(at);
+
+        // Closed sockets drop out here, so the map never outgrows the socket table
+        *samples = next_samples;
+        *self.rates.write().unwrap() = rates;
+        *self.stats.write().unwrap() = stats;
+    }
+}
+
+impl SnapshotObserver for ThroughputTracker {
+    fn name(&self) -> &'static str {
+        "throughput"
+    }
+
+    fn observe(&self, tick: &MonitorTick) {
+        self.update(tick.at, tick.connections);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+    use chrono::Duration;
+
+    fn conn(inode: u64, sent: u64, received: u64) -> ConnectionInfo {
+        let mut conn = ConnectionInfo::new(Protocol::TCP, "10.0.0.2".to_string(), 50000 + inode as u16, "10.0.0.9".to_string(), 443, ConnectionState::Established, 7, "curl".to_string());
+        conn.socket.inode = Some(inode);
+        conn.socket.bytes_sent = Some(sent);
+        conn.socket.bytes_received = Some(received);
+        conn
+    }
+
+    #[test]
+    fn test_rates_between_ticks() {
+        let tracker = ThroughputTracker::new();
+        let t0 = Utc::now();
+        tracker.update(t0, &[conn(1, 1_000, 5_000), conn(2, 100, 100)]);
+        assert_eq!(tracker.stats().active_connections, 0);
+
+        // Inode 2 was reused by a new socket: its counters went backwards
+        tracker.update(t0 + Duration::seconds(2), &[conn(1, 3_000, 25_000), conn(2, 10, 10), conn(3, 50, 50)]);
+        let stats = tracker.stats();
+        assert_eq!((stats.send_rate, stats.recv_rate), (1_000.0, 10_000.0));
+        assert_eq!(stats.active_connections, 1);
+        assert_eq!(stats.top[0].local_port, 50001);
+
+        let mut snapshot = vec![conn(1, 3_000, 25_000), conn(2, 10, 10)];
+        tracker.annotate(&mut snapshot);
+        assert_eq!(snapshot[0].socket.recv_rate, Some(10_000.0));
+        assert_eq!(snapshot[1].socket.recv_rate, None);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::models::{ConnectionState, Protocol};
+use crate::monitor::{MonitorTick, SnapshotObserver};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+
+/// Bounds for `LeakThresholds::growth_samples`
+const MIN_GROWTH_SAMPLES: u32 = 3;
+const MAX_GROWTH_SAMPLES: u32 = 360;
+
+/// When a process's socket counts count as a leak
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeakThresholds {
+    /// Consecutive monitor ticks over which a count must never drop
+    pub growth_samples: u32,
+    /// How much it must have grown across those ticks
+    pub min_growth: u32,
+    /// CLOSE_WAIT sockets one process may hold before it is flagged;
+    /// these are connections the peer closed and the process never did
+    pub close_wait_threshold: u32,
+}
+
+impl Default for LeakThresholds {
+    fn default() -> Self {
+        Self {
+            growth_samples: 6,
+            min_growth: 20,
+            close_wait_threshold: 25,
+        }
+    }
+}
+
+impl LeakThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_GROWTH_SAMPLES..=MAX_GROWTH_SAMPLES).contains(&self.growth_samples) {
+            return Err(format!(
+                "leak growth samples must be {}-{}, got {}",
+                MIN_GROWTH_SAMPLES, MAX_GROWTH_SAMPLES, self.growth_samples
+            ));
+        }
+        if self.min_growth == 0 || self.close_wait_threshold == 0 {
+            return Err("leak thresholds must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakKind {
+    /// The count rose on every tick of the window (or held) and grew by `min_growth`
+    Growth,
+    /// CLOSE_WAIT sockets piled up past `close_wait_threshold`
+    CloseWait,
+}
+
+/// A process whose sockets in one state look like a leak
+#[derive(Debug, Clone, Serialize)]
+pub struct LeakAlert {
+    pub pid: u32,
+    pub process_name: String,
+    pub state: String,
+    pub kind: LeakKind,
+    pub count: u32,
+    /// Net change across the window
+    pub growth: i64,
+    /// First tick the condition held, continuously since
+    pub since: DateTime<Utc>,
+    /// Counts for `state` over the window, oldest first
+    pub samples: Vec<u32>,
+    pub message: String,
+}
+
+struct ProcessTrend {
+    process_name: String,
+    /// Per-state counts, newest last, at most `growth_samples` long
+    samples: VecDeque<HashMap<String, u32>>,
+    flagged_since: HashMap<(String, LeakKind), DateTime<Utc>>,
+}
+
+/// Per-process, per-state socket counts over recent ticks, and the leaks they suggest
+///
+/// Listening sockets and Unix sockets are not counted, nor sockets with no
+/// known owner (pid 0). A pid whose process name changes is treated as a new
+/// process, so pid reuse does not read as growth.
+pub struct TrendTracker {
+    thresholds: RwLock<LeakThresholds>,
+    processes: Mutex<HashMap<u32, ProcessTrend>>,
+    alerts: RwLock<Vec<LeakAlert>>,
+}
+
+impl TrendTracker {
+    pub fn new() -> Self {
+        Self {
+            thresholds: RwLock::new(LeakThresholds::default()),
+            processes: Mutex::new(HashMap::new()),
+            alerts: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn set_thresholds(&self, thresholds: LeakThresholds) {
+        *self.thresholds.write().unwrap() = thresholds;
+    }
+
+    /// Leaks flagged on the latest tick, worst first
+    pub fn alerts(&self) -> Vec<LeakAlert> {
+        self.alerts.read().unwrap().clone()
+    }
+
+    fn evaluate(&self, at: DateTime<Utc>, counts: HashMap<u32, (String, HashMap<String, u32>)>) -> Vec<LeakAlert> {
+        let thresholds = self.thresholds.read().unwrap().clone();
+        let window = thresholds.growth_samples as usize;
+        let mut processes = self.processes.lock().unwrap();
+        processes.retain(|pid, _| counts.contains_key(pid));
+
+        let mut alerts = Vec::new();
+        for (pid, (process_name, states)) in counts {
+            let trend = processes.entry(pid).or_insert_with(|| ProcessTrend {
+                process_name: process_name.clone(),
+                samples: VecDeque::new(),
+                flagged_since: HashMap::new(),
+            });
+            if trend.process_name != process_name {
+                *trend = ProcessTrend {
+                    process_name,
+                    samples: VecDeque::new(),
+                    flagged_since: HashMap::new(),
+                };
+            }
+            trend.samples.push_back(states);
+            while trend.samples.len() > window {
+                trend.samples.pop_front();
+            }
+
+            let mut flagged = HashMap::new();
+            let latest = trend.samples.back().cloned().unwrap_or_default();
+            for (state, count) in latest {
+                let series: Vec<u32> = trend
+                    .samples
+                    .iter()
+                    .map(|sample| sample.get(&state).copied().unwrap_or(0))
+                    .collect();
+                let growth = count as i64 - series[0] as i64;
+
+                let mut kinds = Vec::new();
+                if series.len() == window
+                    && series.windows(2).all(|pair| pair[1] >= pair[0])
+                    && growth >= thresholds.min_growth as i64
+                {
+                    kinds.push(LeakKind::Growth);
+                }
+                if state == ConnectionState::CloseWait.to_string() && count >= thresholds.close_wait_threshold {
+                    kinds.push(LeakKind::CloseWait);
+                }
+
+                for kind in kinds {
+                    let key = (state.clone(), kind);
+                    let since = trend.flagged_since.get(&key).copied().unwrap_or(at);
+                    if since == at {
+                        println!("Monitor: possible socket leak in {} ({}): {} {} sockets", trend.process_name, pid, count, state);
+                    }
+                    flagged.insert(key, since);
+                    alerts.push(LeakAlert {
+                        pid,
+                        process_name: trend.process_name.clone(),
+                        state: state.clone(),
+                        kind,
+                        count,
+                        growth,
+                        since,
+                        samples: series.clone(),
+                        message: match kind {
+                            LeakKind::Growth => format!(
+                                "{} sockets grew by {} over the last {} ticks without ever dropping",
+                                state,
+                                growth,
+                                series.len()
+                            ),
+                            LeakKind::CloseWait => format!(
+                                "{} connections closed by the peer but never closed by the process",
+                                count
+                            ),
+                        },
+                    });
+                }
+            }
+            trend.flagged_since = flagged;
+        }
+
+        alerts.sort_by(|a, b| b.count.cmp(&a.count).then(a.pid.cmp(&b.pid)));
+        alerts
+    }
+}
+
+impl SnapshotObserver for TrendTracker {
+    fn name(&self) -> &'static str {
+        "trends"
+    }
+
+    fn observe(&self, tick: &MonitorTick) {
+        let mut counts: HashMap<u32, (String, HashMap<String, u32>)> = HashMap::new();
+        for conn in tick.connections {
+            if conn.pid == 0 || conn.state.is_listening() || matches!(conn.protocol, Protocol::Unix(_)) {
+                continue;
+            }
+            let (_, states) = counts
+                .entry(conn.pid)
+                .or_insert_with(|| (conn.process_name.clone(), HashMap::new()));
+            *states.entry(conn.state.to_string()).or_insert(0) += 1;
+        }
+        let alerts = self.evaluate(tick.at, counts);
+        *self.alerts.write().unwrap() = alerts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionInfo;
+    use crate::monitor::Monitor;
+    use chrono::Duration as ChronoDuration;
+
+    fn sockets(count: usize, state: ConnectionState, pid: u32, process: &str) -> Vec<ConnectionInfo> {
+        (0..count)
+            .map(|i| {
+                ConnectionInfo::new(Protocol::TCP, "10.0.0.1".to_string(), 40000 + i as u16, "10.0.0.5".to_string(), 443, state.clone(), pid, process.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flags_growth_and_close_wait() {
+        let monitor = Monitor::new();
+        monitor.trends().set_thresholds(LeakThresholds {
+            growth_samples: 4,
+            min_growth: 6,
+            close_wait_threshold: 10,
+        });
+        let t0 = Utc::now();
+
+        // "leaky" gains 3 ESTABLISHED per tick; "steady" goes up and down
+        for (tick, steady) in [5, 9, 4, 8, 3].into_iter().enumerate() {
+            let mut connections = sockets(3 * tick, ConnectionState::Established, 10, "leaky");
+            connections.extend(sockets(steady, ConnectionState::Established, 20, "steady"));
+            connections.extend(sockets(12, ConnectionState::CloseWait, 30, "stuck"));
+            monitor.tick(t0 + ChronoDuration::seconds(tick as i64), &connections);
+        }
+
+        let alerts = monitor.trends().alerts();
+        assert_eq!(alerts.len(), 2);
+        let growth = alerts.iter().find(|a| a.kind == LeakKind::Growth).unwrap();
+        assert_eq!((growth.pid, growth.count, growth.growth), (10, 12, 9));
+        assert_eq!(growth.samples, vec![3, 6, 9, 12]);
+        assert_eq!(growth.since, t0 + ChronoDuration::seconds(4));
+        let close_wait = alerts.iter().find(|a| a.kind == LeakKind::CloseWait).unwrap();
+        assert_eq!((close_wait.pid, close_wait.count), (30, 12));
+        assert_eq!(close_wait.since, t0);
+
+        // Same pid, different program: the history starts over
+        monitor.tick(t0 + ChronoDuration::seconds(5), &sockets(15, ConnectionState::Established, 10, "other"));
+        assert!(monitor.trends().alerts().is_empty());
+    }
+}
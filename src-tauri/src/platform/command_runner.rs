@@ -0,0 +1,260 @@
+use crate::platform::NetworkError;
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Default time an external command may run before it is killed
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default cap on captured stdout/stderr, per stream
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 32 * 1024 * 1024;
+
+/// How often the watchdog checks on the child process
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Extra time the output readers get after the child exits near its deadline
+const READER_GRACE: Duration = Duration::from_millis(500);
+
+/// Captured result of a finished command
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs external commands under a watchdog
+///
+/// Tools such as lsof can block forever (e.g., on stale NFS mounts) or flood
+/// stdout. The runner kills the child once the timeout elapses or once either
+/// output stream exceeds the size cap, and always captures stderr so failures
+/// can be reported with the tool's own message.
+#[derive(Debug, Clone)]
+pub struct CommandRunner {
+    timeout: Duration,
+    max_output_bytes: usize,
+}
+
+impl CommandRunner {
+    pub fn new() -> Self {
+        Self {
+            timeout: DEFAULT_COMMAND_TIMEOUT,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    /// Run `program` with `args`, returning its output only if it exits successfully
+    pub fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput, NetworkError> {
+        let output = self.run_unchecked(program, args)?;
+
+        if !output.status.success() {
+            return Err(NetworkError::CommandFailed(format!(
+                "{} exited with {}: {}",
+                program,
+                output.status,
+                output.stderr.trim()
+            )));
+        }
+
+        Ok(output)
+    }
+
+    /// Run `program` with `args`, returning its output whatever the exit status
+    pub fn run_unchecked(&self, program: &str, args: &[&str]) -> Result<CommandOutput, NetworkError> {
+        let deadline = Instant::now() + self.timeout;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => NetworkError::CommandNotFound(format!(
+                    "{} command not found. Please install {} to view network connections.",
+                    program, program
+                )),
+                std::io::ErrorKind::PermissionDenied => {
+                    NetworkError::PermissionDenied(format!("Not allowed to execute {}: {}", program, e))
+                }
+                _ => NetworkError::IoError(format!("Failed to execute {}: {}", program, e)),
+            })?;
+
+        let overflow = Arc::new(AtomicBool::new(false));
+        let stdout = spawn_capped_reader(child.stdout.take(), self.max_output_bytes, overflow.clone());
+        let stderr = spawn_capped_reader(child.stderr.take(), self.max_output_bytes, overflow.clone());
+
+        let status = self.wait_with_watchdog(&mut child, program, &overflow, deadline)?;
+
+        // A grandchild that inherited the pipes keeps them open after the child exits
+        let readers_deadline = deadline.max(Instant::now() + READER_GRACE);
+        let (Some(stdout), Some(stderr)) = (join_reader(stdout, readers_deadline), join_reader(stderr, readers_deadline)) else {
+            return Err(NetworkError::Timeout(format!(
+                "{} exited but its output stayed open (held by a child process?)",
+                program
+            )));
+        };
+        // The readers may have hit the cap after the watchdog last looked
+        if overflow.load(Ordering::SeqCst) {
+            return Err(self.output_too_large(program));
+        }
+
+        Ok(CommandOutput {
+            status,
+            stdout: String::from_utf8_lossy(&stdout).to_string(),
+            stderr: String::from_utf8_lossy(&stderr).to_string(),
+        })
+    }
+
+    fn output_too_large(&self, program: &str) -> NetworkError {
+        NetworkError::OutputTooLarge(format!(
+            "{} produced more than {} bytes of output",
+            program, self.max_output_bytes
+        ))
+    }
+
+    /// Wait for the child to exit, killing it on timeout or output overflow
+    fn wait_with_watchdog(
+        &self,
+        child: &mut Child,
+        program: &str,
+        overflow: &AtomicBool,
+        deadline: Instant,
+    ) -> Result<ExitStatus, NetworkError> {
+        loop {
+            if overflow.load(Ordering::SeqCst) {
+                kill_child(child);
+                return Err(self.output_too_large(program));
+            }
+
+            match child.try_wait() {
+                Ok(Some(status)) => return Ok(status),
+                Ok(None) => {}
+                Err(e) => {
+                    kill_child(child);
+                    return Err(NetworkError::IoError(format!("Failed to wait for {}: {}", program, e)));
+                }
+            }
+
+            if Instant::now() >= deadline {
+                kill_child(child);
+                return Err(NetworkError::Timeout(format!(
+                    "{} did not finish within {}s and was killed",
+                    program,
+                    self.timeout.as_secs_f32()
+                )));
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Drain a pipe on a background thread, flagging overflow once `limit` is exceeded
+fn spawn_capped_reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    limit: usize,
+    overflow: Arc<AtomicBool>,
+) -> Option<JoinHandle<Vec<u8>>> {
+    let mut pipe = pipe?;
+    Some(thread::spawn(move || {
+        let mut captured = Vec::new();
+        let mut chunk = [0u8; 8192];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if captured.len() + n > limit {
+                        overflow.store(true, Ordering::SeqCst);
+                        break;
+                    }
+                    captured.extend_from_slice(&chunk[..n]);
+                }
+            }
+        }
+        captured
+    }))
+}
+
+/// Collect a reader's output, or `None` if it is still blocked at `deadline`
+///
+/// A reader left behind keeps running until the pipe closes; it is detached.
+fn join_reader(reader: Option<JoinHandle<Vec<u8>>>, deadline: Instant) -> Option<Vec<u8>> {
+    let Some(handle) = reader else {
+        return Some(Vec::new());
+    };
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Some(handle.join().unwrap_or_default())
+}
+
+fn kill_child(child: &mut Child) {
+    let _ = child.kill();
+    // Reap the process so it does not linger as a zombie
+    let _ = child.wait();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures_stdout_and_stderr() {
+        let output = CommandRunner::new()
+            .run_unchecked("sh", &["-c", "echo out; echo err >&2; exit 3"])
+            .unwrap();
+        assert_eq!(output.stdout.trim(), "out");
+        assert_eq!(output.stderr.trim(), "err");
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn test_failure_maps_to_distinct_errors() {
+        let runner = CommandRunner::new().with_timeout(Duration::from_millis(200));
+
+        assert!(matches!(runner.run("sh", &["-c", "exit 1"]), Err(NetworkError::CommandFailed(_))));
+        assert!(matches!(runner.run("sleep", &["5"]), Err(NetworkError::Timeout(_))));
+        assert!(matches!(
+            runner.run("portviewer-no-such-command", &[]),
+            Err(NetworkError::CommandNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_output_cap_kills_child() {
+        let runner = CommandRunner::new().with_max_output_bytes(64 * 1024);
+        assert!(matches!(runner.run("yes", &[]), Err(NetworkError::OutputTooLarge(_))));
+
+        // Overflowing right before a quick exit still counts
+        let runner = CommandRunner::new().with_max_output_bytes(1024);
+        assert!(matches!(
+            runner.run("sh", &["-c", "head -c 4096 /dev/zero"]),
+            Err(NetworkError::OutputTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_inherited_pipe_does_not_hang() {
+        let runner = CommandRunner::new().with_timeout(Duration::from_millis(300));
+        let started = Instant::now();
+        let result = runner.run("sh", &["-c", "sleep 5 & echo started"]);
+        assert!(matches!(result, Err(NetworkError::Timeout(_))));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
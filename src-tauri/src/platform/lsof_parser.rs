@@ -1,70 +1,64 @@
-use crate::models::{ConnectionInfo, Protocol, ConnectionState};
+use crate::models::{ConnectionInfo, Protocol, ConnectionState, SocketDetails, UnixSocketType};
 use crate::platform::NetworkError;
-use std::process::Command;
+use crate::platform::command_runner::CommandRunner;
+use std::time::{Duration, Instant};
+
+/// Arguments shared by every lsof invocation
+///
+/// `-b` avoids kernel calls that can block (stale NFS mounts), `-w` suppresses
+/// the warnings `-b` would otherwise print, `-n`/`-P` skip name resolution.
+const LSOF_BASE_ARGS: [&str; 4] = ["-b", "-w", "-n", "-P"];
+
+/// Budget for all lsof runs of one collection (`-i` and `-U` together);
+/// kept below the collector's own timeout
+const LSOF_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Even busy hosts stay well below this much `-i` output
+const LSOF_MAX_OUTPUT_BYTES: usize = 64 * 1024 * 1024;
 
 /// Unified lsof parser for macOS and Linux
 /// 
 /// Since lsof command format and output are identical on both platforms,
 /// this provides a shared implementation to avoid code duplication
-pub struct LsofParser;
+pub struct LsofParser {
+    runner: CommandRunner,
+}
 
 impl LsofParser {
     pub fn new() -> Self {
-        Self
+        Self {
+            runner: CommandRunner::new()
+                .with_timeout(LSOF_TIMEOUT)
+                .with_max_output_bytes(LSOF_MAX_OUTPUT_BYTES),
+        }
+    }
+
+    /// Run lsof with the base arguments plus `selection`, returning stdout
+    ///
+    /// lsof is killed at `deadline`, which all runs of one collection share.
+    fn execute_lsof(&self, selection: &str, deadline: Instant) -> Result<String, NetworkError> {
+        let mut args = vec![selection];
+        args.extend_from_slice(&LSOF_BASE_ARGS);
+        let runner = self.runner.clone().with_timeout(deadline.saturating_duration_since(Instant::now()));
+        Ok(runner.run("lsof", &args)?.stdout)
     }
 
     /// Execute lsof command to get network connections by protocol
     pub fn execute_lsof_by_protocol(&self, protocol: &str) -> Result<String, NetworkError> {
-        let protocol_arg = format!("-i{}", protocol);
-        let output = Command::new("lsof")
-            .args(&[&protocol_arg, "-n", "-P"])
-            .output()
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    NetworkError::CommandNotFound("lsof command not found. Please install lsof to view network connections.".to_string())
-                } else {
-                    NetworkError::CommandFailed(format!("Failed to execute lsof: {}", e))
-                }
-            })?;
-
-        if !output.status.success() {
-            return Err(NetworkError::CommandFailed(format!(
-                "lsof command failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
-            )));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        self.execute_lsof(&format!("-i{}", protocol), Instant::now() + LSOF_TIMEOUT)
     }
 
     /// Execute lsof command to get all network connections
-    pub fn execute_lsof_all(&self) -> Result<String, NetworkError> {
-        let output = Command::new("lsof")
-            .args(&["-i", "-n", "-P"])
-            .output()
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    NetworkError::CommandNotFound("lsof command not found. Please install lsof to view network connections.".to_string())
-                } else {
-                    NetworkError::CommandFailed(format!("Failed to execute lsof: {}", e))
-                }
-            })?;
-
-        if !output.status.success() {
-            return Err(NetworkError::CommandFailed(format!(
-                "lsof command failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
-            )));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    pub fn execute_lsof_all(&self, deadline: Instant) -> Result<String, NetworkError> {
+        self.execute_lsof("-i", deadline)
     }
 
     /// Parse lsof output to get all connections
     pub fn parse_all_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
         let mut connections = Vec::new();
+        let deadline = Instant::now() + LSOF_TIMEOUT;
         
-        let lsof_output = self.execute_lsof_all()?;
+        let lsof_output = self.execute_lsof_all(deadline)?;
         
         for line in lsof_output.lines().skip(1) { // Skip header
             // Try parsing as TCP connection
@@ -76,10 +70,27 @@ impl LsofParser {
                 connections.push(conn);
             }
         }
+
+        // Unix sockets need a separate `-U` run; losing them should not lose the IP rows
+        match self.parse_unix_sockets(deadline) {
+            Ok(unix) => connections.extend(unix),
+            Err(e) => println!("lsof -U failed, skipping Unix sockets: {}", e),
+        }
         
         Ok(connections)
     }
 
+    /// Parse lsof output to get Unix domain sockets
+    pub fn parse_unix_sockets(&self, deadline: Instant) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let lsof_output = self.execute_lsof("-U", deadline)?;
+
+        Ok(lsof_output
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(|line| self.parse_lsof_unix_line(line))
+            .collect())
+    }
+
     /// Parse lsof output to get TCP connections
     pub fn parse_tcp_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
         let mut connections = Vec::new();
@@ -209,6 +220,68 @@ impl LsofParser {
         ))
     }
 
+    /// Parse lsof output line for Unix domain sockets
+    ///
+    /// Linux: `sshd 812 root 3u unix 0x0000 0t0 21345 /run/sshd.sock type=STREAM (LISTEN)`
+    /// macOS has no NODE column and no `type=`/state suffix, so those default.
+    fn parse_lsof_unix_line(&self, line: &str) -> Option<ConnectionInfo> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 8 || fields[4] != "unix" {
+            return None;
+        }
+
+        let process_name = fields[0].to_string();
+        let pid = fields[1].parse::<u32>().ok()?;
+        let rest = &fields[7..];
+
+        let inode = rest.first().and_then(|node| node.parse::<u64>().ok());
+        let socket_type = rest
+            .iter()
+            .find_map(|field| field.strip_prefix("type="))
+            .map(|kind| match kind {
+                "DGRAM" => UnixSocketType::Datagram,
+                "SEQPACKET" => UnixSocketType::SeqPacket,
+                _ => UnixSocketType::Stream,
+            })
+            .unwrap_or(UnixSocketType::Stream);
+        let path = rest
+            .iter()
+            .find(|field| field.starts_with('/') || field.starts_with('@'))
+            .map(|path| path.to_string())
+            .unwrap_or_default();
+        let remote_address = rest
+            .iter()
+            .find_map(|field| field.strip_prefix("->"))
+            .filter(|peer| !peer.is_empty())
+            .unwrap_or("*")
+            .to_string();
+
+        let state = if rest.contains(&"(LISTEN)") {
+            ConnectionState::Listening
+        } else if rest.contains(&"(CONNECTED)") || remote_address != "*" {
+            ConnectionState::Established
+        } else {
+            ConnectionState::Closed
+        };
+
+        Some(
+            ConnectionInfo::new(
+                Protocol::Unix(socket_type),
+                path,
+                0,
+                remote_address,
+                0,
+                state,
+                pid,
+                process_name,
+            )
+            .with_socket_details(SocketDetails {
+                inode,
+                ..SocketDetails::default()
+            }),
+        )
+    }
+
     /// Parse address string in format "ip:port" or "ip.port"
     fn parse_address(&self, addr: &str) -> Option<(String, u16)> {
         // Handle IPv6 addresses [::1]:port or IPv4 addresses ip:port
@@ -266,6 +339,37 @@ impl LsofParser {
 
     /// Check if lsof command is available
     pub fn is_supported(&self) -> bool {
-        Command::new("lsof").arg("-v").output().is_ok()
+        self.runner
+            .clone()
+            .with_timeout(Duration::from_secs(2))
+            .run_unchecked("lsof", &["-v"])
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lsof_unix_line() {
+        let parser = LsofParser::new();
+
+        let conn = parser
+            .parse_lsof_unix_line("sshd 812 root 3u unix 0x000000001234abcd 0t0 21345 /run/sshd.sock type=STREAM (LISTEN)")
+            .unwrap();
+        assert_eq!(conn.protocol, Protocol::Unix(UnixSocketType::Stream));
+        assert_eq!(conn.local_address, "/run/sshd.sock");
+        assert_eq!(conn.state, ConnectionState::Listening);
+        assert_eq!(conn.socket.inode, Some(21345));
+
+        let conn = parser
+            .parse_lsof_unix_line("launchd 1 root 7u unix 0x6fb2e1 0t0 ->0x6fb2a9")
+            .unwrap();
+        assert_eq!(conn.remote_address, "0x6fb2a9");
+        assert_eq!(conn.state, ConnectionState::Established);
+        assert_eq!(conn.socket.inode, None);
+
+        assert!(parser.parse_lsof_unix_line("nginx 42 www 6u IPv4 0x1 0t0 TCP *:80 (LISTEN)").is_none());
     }
 }
@@ -42,6 +42,12 @@ pub enum NetworkError {
     ParseError(String),
     /// Generic I/O error
     IoError(String),
+    /// Operation did not finish in time (e.g., lsof hung on a stale mount)
+    Timeout(String),
+    /// External command produced more output than we are willing to buffer
+    OutputTooLarge(String),
+    /// Request was abandoned because a newer one superseded it
+    Cancelled(String),
 }
 
 impl fmt::Display for NetworkError {
@@ -55,6 +61,9 @@ impl fmt::Display for NetworkError {
             NetworkError::CommandNotFound(msg) => write!(f, "Command not found: {}", msg),
             NetworkError::ParseError(msg) => write!(f, "Parse error: {}", msg),
             NetworkError::IoError(msg) => write!(f, "I/O error: {}", msg),
+            NetworkError::Timeout(msg) => write!(f, "Timed out: {}", msg),
+            NetworkError::OutputTooLarge(msg) => write!(f, "Output too large: {}", msg),
+            NetworkError::Cancelled(msg) => write!(f, "Request cancelled: {}", msg),
         }
     }
 }
@@ -76,9 +85,16 @@ pub mod windows;
 #[cfg(target_os = "linux")]
 pub mod linux;
 
+#[cfg(target_os = "linux")]
+pub mod procfs;
+
+#[cfg(target_os = "linux")]
+pub mod sock_diag;
+
 #[cfg(target_os = "macos")]
 pub mod macos;
 
 // Unified and shared modules
 pub mod unified;
 pub mod lsof_parser;
+pub mod command_runner;
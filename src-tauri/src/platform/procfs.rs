@@ -0,0 +1,777 @@
+use crate::models::{ConnectionInfo, Protocol, ConnectionState, SocketDetails, SocketTimer, UnixSocketType};
+use crate::platform::{NetworkProvider, NetworkError};
+use crate::platform::sock_diag;
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Clock ticks per second used by the `tm->when` column (USER_HZ, 100 on all mainstream arches)
+const USER_HZ: u64 = 100;
+
+/// `__SO_ACCEPTCON` in the Flags column of `/proc/net/unix`: socket is listening
+const UNIX_FLAG_ACCEPTCON: u32 = 0x0001_0000;
+
+/// Best-effort sock_diag lookups; static because a provider is built for each run
+static TCP_INFO: OptionalLookup = OptionalLookup::new("tcp_info");
+static UNIX_PEERS: OptionalLookup = OptionalLookup::new("unix peer");
+
+/// A sock_diag dump the provider can do without
+///
+/// A failure is logged once per run of failures rather than on every refresh.
+/// Permanent ones (no permission, kernel without the diag module, seccomp)
+/// stop further attempts; timeouts and other transient errors are retried.
+struct OptionalLookup {
+    name: &'static str,
+    unsupported: AtomicBool,
+    failing: AtomicBool,
+}
+
+impl OptionalLookup {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            unsupported: AtomicBool::new(false),
+            failing: AtomicBool::new(false),
+        }
+    }
+
+    fn run<T>(&self, lookup: impl FnOnce() -> Result<T, NetworkError>) -> Option<T> {
+        if self.unsupported.load(Ordering::Relaxed) {
+            return None;
+        }
+        match lookup() {
+            Ok(value) => {
+                self.failing.store(false, Ordering::Relaxed);
+                Some(value)
+            }
+            Err(e) => {
+                let permanent = matches!(e, NetworkError::PermissionDenied(_) | NetworkError::UnsupportedPlatform(_));
+                let first = !self.failing.swap(true, Ordering::Relaxed);
+                if permanent {
+                    self.unsupported.store(true, Ordering::Relaxed);
+                    eprintln!("Backend Error: sock_diag {} lookup failed, no longer trying: {}", self.name, e);
+                } else if first {
+                    eprintln!("Backend Error: sock_diag {} lookup failed, will retry: {}", self.name, e);
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Maps a kernel state code to our connection state
+type StateMapper = fn(u8) -> ConnectionState;
+
+/// TCP socket tables
+const TCP_TABLES: [(&str, Protocol, StateMapper); 2] = [
+    ("tcp", Protocol::TCP, tcp_state),
+    ("tcp6", Protocol::TCP, tcp_state),
+];
+
+/// UDP socket tables
+const UDP_TABLES: [(&str, Protocol, StateMapper); 2] = [
+    ("udp", Protocol::UDP, datagram_state),
+    ("udp6", Protocol::UDP, datagram_state),
+];
+
+/// Remaining IP socket tables sharing the tcp/udp line format
+///
+/// raw/raw6 report the IP protocol number in the port column and icmp/icmp6
+/// (unprivileged ping sockets) the echo identifier.
+fn extra_inet_tables() -> [(&'static str, Protocol, StateMapper); 6] {
+    [
+        ("raw", Protocol::Raw, datagram_state),
+        ("raw6", Protocol::Raw, datagram_state),
+        ("icmp", Protocol::ICMP, datagram_state),
+        ("icmp6", Protocol::ICMP, datagram_state),
+        ("udplite", Protocol::Other("UDPLITE".to_string()), datagram_state),
+        ("udplite6", Protocol::Other("UDPLITE".to_string()), datagram_state),
+    ]
+}
+
+/// Owning process of a socket inode
+#[derive(Debug, Clone)]
+pub struct SocketOwner {
+    pub pid: u32,
+    pub process_name: String,
+}
+
+/// One parsed row of `/proc/net/{tcp,udp}[6]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcNetEntry {
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub state_code: u8,
+    pub details: SocketDetails,
+}
+
+/// One parsed row of `/proc/net/sctp/eps` (listening endpoint) or `assocs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SctpEntry {
+    pub local_address: String,
+    pub local_port: u16,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub state: ConnectionState,
+    pub details: SocketDetails,
+}
+
+/// One parsed row of `/proc/net/unix`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnixEntry {
+    pub inode: u64,
+    pub socket_type: UnixSocketType,
+    pub state: ConnectionState,
+    /// Filesystem path, `@name` for abstract sockets, empty when unnamed
+    pub path: String,
+}
+
+/// Linux provider reading the kernel socket tables in `/proc/net` directly
+///
+/// Unlike lsof this needs no external command and exposes socket-level detail
+/// (inode, uid, queues, retransmits, timers). Owning processes are found by
+/// matching socket inodes against `/proc/<pid>/fd` links, which only works for
+/// processes we are allowed to inspect; other sockets are reported with pid 0.
+///
+/// `/proc/net` only shows the app's own network namespace. With
+/// `with_all_namespaces(true)` the tables of every other namespace (containers,
+/// `ip netns`) are read through `/proc/<pid>/net` of one member process.
+pub struct ProcfsNetworkProvider {
+    all_namespaces: bool,
+}
+
+impl ProcfsNetworkProvider {
+    pub fn new() -> Self {
+        Self { all_namespaces: false }
+    }
+
+    pub fn with_all_namespaces(mut self, all_namespaces: bool) -> Self {
+        self.all_namespaces = all_namespaces;
+        self
+    }
+
+    /// Read every socket table of one network namespace, tagging rows with it
+    fn read_namespace(
+        &self,
+        net_dir: &Path,
+        namespace: Option<u64>,
+        owners: &HashMap<u64, SocketOwner>,
+        unix_peers: &HashMap<u64, u64>,
+    ) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let tables: Vec<_> = TCP_TABLES.into_iter().chain(UDP_TABLES).chain(extra_inet_tables()).collect();
+
+        let mut connections = self.read_tables(net_dir, &tables, owners)?;
+        connections.extend(self.read_sctp_tables(net_dir, owners)?);
+        connections.extend(self.read_unix_table(net_dir, owners, unix_peers)?);
+
+        if namespace.is_some() {
+            for conn in &mut connections {
+                conn.context.net_namespace = namespace;
+            }
+        }
+        Ok(connections)
+    }
+
+    /// Read one socket table (e.g. `tcp6`) from a `/proc/.../net` directory
+    fn read_table(
+        &self,
+        net_dir: &Path,
+        table: &str,
+        protocol: Protocol,
+        state_of: StateMapper,
+        owners: &HashMap<u64, SocketOwner>,
+    ) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let content = read_net_file(&net_dir.join(table))?;
+
+        let connections = content
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(parse_proc_net_line)
+            .map(|entry| {
+                let state = state_of(entry.state_code);
+                let (pid, process_name) = owner_of(entry.details.inode, owners);
+                let (remote_address, remote_port) = if entry.remote_port == 0 && is_unspecified(&entry.remote_address) {
+                    ("*".to_string(), 0)
+                } else {
+                    (entry.remote_address, entry.remote_port)
+                };
+
+                ConnectionInfo::new(
+                    protocol.clone(),
+                    entry.local_address,
+                    entry.local_port,
+                    remote_address,
+                    remote_port,
+                    state,
+                    pid,
+                    process_name,
+                )
+                .with_socket_details(entry.details)
+            })
+            .collect();
+
+        Ok(connections)
+    }
+
+    /// Read `/proc/.../net/unix`, resolving peers through sock_diag when possible
+    fn read_unix_table(
+        &self,
+        net_dir: &Path,
+        owners: &HashMap<u64, SocketOwner>,
+        peers: &HashMap<u64, u64>,
+    ) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let content = read_net_file(&net_dir.join("unix"))?;
+        let entries: Vec<UnixEntry> = content.lines().skip(1).filter_map(parse_unix_line).collect();
+
+        let paths: HashMap<u64, &str> = entries.iter().map(|e| (e.inode, e.path.as_str())).collect();
+
+        let connections = entries
+            .iter()
+            .map(|entry| {
+                let (pid, process_name) = owner_of(Some(entry.inode), owners);
+                let peer_inode = peers.get(&entry.inode).copied();
+                let remote_address = match peer_inode.and_then(|peer| paths.get(&peer)) {
+                    Some(path) if !path.is_empty() => path.to_string(),
+                    _ => "*".to_string(),
+                };
+
+                ConnectionInfo::new(
+                    Protocol::Unix(entry.socket_type),
+                    entry.path.clone(),
+                    0,
+                    remote_address,
+                    0,
+                    entry.state.clone(),
+                    pid,
+                    process_name,
+                )
+                .with_socket_details(SocketDetails {
+                    inode: Some(entry.inode),
+                    peer_inode,
+                    ..SocketDetails::default()
+                })
+            })
+            .collect();
+
+        Ok(connections)
+    }
+
+    /// Read SCTP endpoints and associations (only present with the sctp module loaded)
+    fn read_sctp_tables(
+        &self,
+        net_dir: &Path,
+        owners: &HashMap<u64, SocketOwner>,
+    ) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let endpoints = read_net_file(&net_dir.join("sctp/eps"))?;
+        let associations = read_net_file(&net_dir.join("sctp/assocs"))?;
+
+        let entries = endpoints
+            .lines()
+            .skip(1)
+            .filter_map(parse_sctp_endpoint_line)
+            .chain(associations.lines().skip(1).filter_map(parse_sctp_assoc_line));
+
+        Ok(entries
+            .map(|entry| {
+                let (pid, process_name) = owner_of(entry.details.inode, owners);
+                ConnectionInfo::new(
+                    Protocol::SCTP,
+                    entry.local_address,
+                    entry.local_port,
+                    entry.remote_address,
+                    entry.remote_port,
+                    entry.state,
+                    pid,
+                    process_name,
+                )
+                .with_socket_details(entry.details)
+            })
+            .collect())
+    }
+
+    fn read_tables(
+        &self,
+        net_dir: &Path,
+        tables: &[(&str, Protocol, StateMapper)],
+        owners: &HashMap<u64, SocketOwner>,
+    ) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let mut connections = Vec::new();
+        for (table, protocol, state_of) in tables {
+            connections.extend(self.read_table(net_dir, table, protocol.clone(), *state_of, owners)?);
+        }
+        Ok(connections)
+    }
+}
+
+impl NetworkProvider for ProcfsNetworkProvider {
+    fn get_all_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let owners = socket_owners();
+        // Peer lookup is best effort: without it Unix rows simply lack a remote end.
+        // Socket inodes are global, so one dump serves every namespace we read.
+        let unix_peers = UNIX_PEERS.run(sock_diag::unix_peers).unwrap_or_default();
+
+        let host_namespace = net_namespace_of(Path::new("/proc/self"));
+        let mut connections = self.read_namespace(Path::new("/proc/net"), host_namespace, &owners, &unix_peers)?;
+
+        if self.all_namespaces {
+            for (namespace, pid) in net_namespaces() {
+                if Some(namespace) == host_namespace {
+                    continue;
+                }
+                let net_dir = PathBuf::from(format!("/proc/{}/net", pid));
+                // The member process may exit mid-scan; skip that namespace rather than fail
+                match self.read_namespace(&net_dir, Some(namespace), &owners, &unix_peers) {
+                    Ok(rows) => connections.extend(rows),
+                    Err(e) => println!("Skipping network namespace {} (pid {}): {}", namespace, pid, e),
+                }
+            }
+        }
+
+        // RTT, cwnd and byte counters are best effort too; /proc/net/tcp has none of them
+        if let Some(infos) = TCP_INFO.run(sock_diag::tcp_info) {
+            for conn in connections.iter_mut().filter(|conn| conn.protocol == Protocol::TCP) {
+                let Some(info) = conn.socket.inode.and_then(|inode| infos.get(&inode)) else {
+                    continue;
+                };
+                conn.socket.rtt_us = Some(info.rtt_us);
+                conn.socket.cwnd = Some(info.cwnd);
+                // bytes_acked counts the SYN as one byte
+                conn.socket.bytes_sent = info.bytes_acked.map(|bytes| bytes.saturating_sub(1));
+                conn.socket.bytes_received = info.bytes_received;
+            }
+        }
+
+        Ok(connections)
+    }
+
+    fn get_tcp_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        self.read_tables(Path::new("/proc/net"), &TCP_TABLES, &socket_owners())
+    }
+
+    fn get_udp_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        self.read_tables(Path::new("/proc/net"), &UDP_TABLES, &socket_owners())
+    }
+
+    fn platform_name(&self) -> &'static str {
+        "Linux"
+    }
+
+    fn is_supported(&self) -> bool {
+        Path::new("/proc/net/tcp").exists()
+    }
+}
+
+/// Network namespace inode of a `/proc/<pid>` (or `/proc/self`) directory
+pub fn net_namespace_of(proc_dir: &Path) -> Option<u64> {
+    let target = fs::read_link(proc_dir.join("ns/net")).ok()?;
+    parse_namespace_link(target.to_str()?)
+}
+
+/// Every network namespace we can see, mapped to one process inside it
+pub fn net_namespaces() -> HashMap<u64, u32> {
+    let mut namespaces = HashMap::new();
+    let Ok(proc_entries) = fs::read_dir("/proc") else {
+        return namespaces;
+    };
+
+    for entry in proc_entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        if let Some(namespace) = net_namespace_of(&entry.path()) {
+            namespaces.entry(namespace).or_insert(pid);
+        }
+    }
+    namespaces
+}
+
+/// Parse a namespace link target of the form `net:[4026531840]`
+fn parse_namespace_link(target: &str) -> Option<u64> {
+    target.strip_prefix("net:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Map every socket inode we can see to the process holding it
+pub fn socket_owners() -> HashMap<u64, SocketOwner> {
+    let mut owners = HashMap::new();
+    let Ok(proc_entries) = fs::read_dir("/proc") else {
+        return owners;
+    };
+
+    for entry in proc_entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        // Permission denied for other users' processes unless running as root
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        let process_name = fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| comm.trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            if let Some(inode) = parse_socket_link(&target.to_string_lossy()) {
+                owners.entry(inode).or_insert_with(|| SocketOwner {
+                    pid,
+                    process_name: process_name.clone(),
+                });
+            }
+        }
+    }
+
+    owners
+}
+
+/// Read a `/proc/net` table; missing tables (e.g. IPv6 disabled) read as empty
+fn read_net_file(path: &Path) -> Result<String, NetworkError> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(NetworkError::FileSystemError(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+fn owner_of(inode: Option<u64>, owners: &HashMap<u64, SocketOwner>) -> (u32, String) {
+    inode
+        .and_then(|inode| owners.get(&inode))
+        .map(|o| (o.pid, o.process_name.clone()))
+        .unwrap_or((0, "unknown".to_string()))
+}
+
+/// Parse an fd link target of the form `socket:[12345]`
+fn parse_socket_link(target: &str) -> Option<u64> {
+    target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Parse one data line of `/proc/net/{tcp,udp}[6]`
+///
+/// Format: `sl local rem st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ...`
+pub fn parse_proc_net_line(line: &str) -> Option<ProcNetEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 10 {
+        return None;
+    }
+
+    let (local_address, local_port) = parse_hex_endpoint(fields[1])?;
+    let (remote_address, remote_port) = parse_hex_endpoint(fields[2])?;
+    let state_code = u8::from_str_radix(fields[3], 16).ok()?;
+
+    let (tx_queue, rx_queue) = fields[4].split_once(':')?;
+    let (timer_code, timer_when) = fields[5].split_once(':')?;
+    let timer_code = u8::from_str_radix(timer_code, 16).ok()?;
+    let (timer, timer_expires_ms) = if timer_code == 0 {
+        (None, None)
+    } else {
+        let ticks = u64::from_str_radix(timer_when, 16).ok()?;
+        (Some(SocketTimer::from(timer_code)), Some(ticks * 1000 / USER_HZ))
+    };
+
+    let details = SocketDetails {
+        inode: fields[9].parse().ok(),
+        uid: fields[7].parse().ok(),
+        rx_queue: u64::from_str_radix(rx_queue, 16).ok(),
+        tx_queue: u64::from_str_radix(tx_queue, 16).ok(),
+        retransmits: u32::from_str_radix(fields[6], 16).ok(),
+        timer,
+        timer_expires_ms,
+        ..SocketDetails::default()
+    };
+
+    Some(ProcNetEntry {
+        local_address,
+        local_port,
+        remote_address,
+        remote_port,
+        state_code,
+        details,
+    })
+}
+
+/// Parse one data line of `/proc/net/unix`
+///
+/// Format: `Num RefCount Protocol Flags Type St Inode [Path]`
+pub fn parse_unix_line(line: &str) -> Option<UnixEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 7 {
+        return None;
+    }
+
+    let flags = u32::from_str_radix(fields[3], 16).ok()?;
+    let socket_type = match u16::from_str_radix(fields[4], 16).ok()? {
+        1 => UnixSocketType::Stream,
+        2 => UnixSocketType::Datagram,
+        5 => UnixSocketType::SeqPacket,
+        _ => return None,
+    };
+    let state_code = u8::from_str_radix(fields[5], 16).ok()?;
+    let inode = fields[6].parse().ok()?;
+    // Paths may contain spaces, so take the remainder of the line
+    let path = fields[7..].join(" ");
+
+    let state = if flags & UNIX_FLAG_ACCEPTCON != 0 {
+        ConnectionState::Listening
+    } else {
+        match state_code {
+            // SS_UNCONNECTED: a bound datagram socket is effectively listening
+            0x01 if socket_type == UnixSocketType::Datagram && !path.is_empty() => ConnectionState::Listening,
+            0x01 => ConnectionState::Closed,
+            0x02 => ConnectionState::SynSent,
+            0x03 => ConnectionState::Established,
+            0x04 => ConnectionState::Closing,
+            _ => ConnectionState::Unknown(state_code as u32),
+        }
+    };
+
+    Some(UnixEntry {
+        inode,
+        socket_type,
+        state,
+        path,
+    })
+}
+
+/// Parse `0100007F:0016` (IPv4) or a 32-digit IPv6 address plus port
+///
+/// The kernel prints each 32-bit word of the address in host byte order,
+/// so the words are converted back with native endianness.
+fn parse_hex_endpoint(endpoint: &str) -> Option<(String, u16)> {
+    let (addr, port) = endpoint.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let address = match addr.len() {
+        8 => Ipv4Addr::from(u32::from_str_radix(addr, 16).ok()?.to_ne_bytes()).to_string(),
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&addr[i * 8..i * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            Ipv6Addr::from(bytes).to_string()
+        }
+        _ => return None,
+    };
+
+    Some((address, port))
+}
+
+fn is_unspecified(address: &str) -> bool {
+    address == "0.0.0.0" || address == "::"
+}
+
+/// Map kernel TCP state codes (include/net/tcp_states.h)
+fn tcp_state(code: u8) -> ConnectionState {
+    match code {
+        0x01 => ConnectionState::Established,
+        0x02 => ConnectionState::SynSent,
+        0x03 => ConnectionState::SynRcvd,
+        0x04 => ConnectionState::FinWait1,
+        0x05 => ConnectionState::FinWait2,
+        0x06 => ConnectionState::TimeWait,
+        0x07 => ConnectionState::Closed,
+        0x08 => ConnectionState::CloseWait,
+        0x09 => ConnectionState::LastAck,
+        // Reported the same way as the lsof backend on this platform
+        0x0A => ConnectionState::Listening,
+        0x0B => ConnectionState::Closing,
+        _ => ConnectionState::Unknown(code as u32),
+    }
+}
+
+/// Datagram-style sockets (UDP, raw, ping) are "established" once connected, otherwise just bound
+fn datagram_state(code: u8) -> ConnectionState {
+    match code {
+        0x01 => ConnectionState::Established,
+        _ => ConnectionState::Listening,
+    }
+}
+
+/// Map SCTP association states (include/uapi/linux/sctp.h `sctp_state_t`)
+fn sctp_state(code: u8) -> ConnectionState {
+    match code {
+        0 => ConnectionState::Closed,
+        1 | 2 => ConnectionState::SynSent, // COOKIE_WAIT, COOKIE_ECHOED
+        3 => ConnectionState::Established,
+        4 | 5 => ConnectionState::FinWait1, // SHUTDOWN_PENDING, SHUTDOWN_SENT
+        6 => ConnectionState::CloseWait,    // SHUTDOWN_RECEIVED
+        7 => ConnectionState::LastAck,      // SHUTDOWN_ACK_SENT
+        _ => ConnectionState::Unknown(code as u32),
+    }
+}
+
+/// Parse one line of `/proc/net/sctp/eps`
+///
+/// Format: `ENDPT SOCK STY SST HBKT LPORT UID INODE LADDRS...`
+pub fn parse_sctp_endpoint_line(line: &str) -> Option<SctpEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 9 {
+        return None;
+    }
+
+    Some(SctpEntry {
+        local_address: primary_sctp_address(&fields[8..])?,
+        local_port: fields[5].parse().ok()?,
+        remote_address: "*".to_string(),
+        remote_port: 0,
+        state: ConnectionState::Listening,
+        details: SocketDetails {
+            uid: fields[6].parse().ok(),
+            inode: fields[7].parse().ok(),
+            ..SocketDetails::default()
+        },
+    })
+}
+
+/// Parse one line of `/proc/net/sctp/assocs`
+///
+/// Format: `ASSOC SOCK STY SST ST HBKT ASSOC-ID TX_QUEUE RX_QUEUE UID INODE
+/// LPORT RPORT LADDRS... <-> RADDRS... HBINT ...`
+pub fn parse_sctp_assoc_line(line: &str) -> Option<SctpEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 16 {
+        return None;
+    }
+
+    let addresses = &fields[13..];
+    let separator = addresses.iter().position(|field| *field == "<->")?;
+
+    Some(SctpEntry {
+        local_address: primary_sctp_address(&addresses[..separator])?,
+        local_port: fields[11].parse().ok()?,
+        remote_address: primary_sctp_address(&addresses[separator + 1..])?,
+        remote_port: fields[12].parse().ok()?,
+        state: sctp_state(fields[4].parse().ok()?),
+        details: SocketDetails {
+            tx_queue: fields[7].parse().ok(),
+            rx_queue: fields[8].parse().ok(),
+            uid: fields[9].parse().ok(),
+            inode: fields[10].parse().ok(),
+            ..SocketDetails::default()
+        },
+    })
+}
+
+/// Pick the primary address (marked `*`) of a multi-homed SCTP address list,
+/// falling back to the first one; the list ends at the first non-address field
+fn primary_sctp_address(fields: &[&str]) -> Option<String> {
+    let addresses: Vec<(bool, IpAddr)> = fields
+        .iter()
+        .map_while(|field| {
+            let primary = field.starts_with('*');
+            field.trim_start_matches('*').parse::<IpAddr>().ok().map(|ip| (primary, ip))
+        })
+        .collect();
+
+    addresses
+        .iter()
+        .find(|(primary, _)| *primary)
+        .or(addresses.first())
+        .map(|(_, ip)| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optional_lookup_retries_transient_errors() {
+        let lookup = OptionalLookup::new("test");
+        assert_eq!(lookup.run(|| Err::<u8, _>(NetworkError::Timeout("slow".to_string()))), None);
+        assert_eq!(lookup.run(|| Ok(1)), Some(1));
+        assert_eq!(lookup.run(|| Err::<u8, _>(NetworkError::PermissionDenied("no".to_string()))), None);
+        assert_eq!(lookup.run(|| Ok(2)), None);
+    }
+
+    #[test]
+    fn test_parse_ipv4_listener() {
+        let line = "   0: 0100007F:1538 00000000:0000 0A 00000000:00000003 00:00000000 00000000   999        0 41234 1 0000000000000000 100 0 0 10 0";
+        let entry = parse_proc_net_line(line).unwrap();
+
+        assert_eq!(entry.local_address, "127.0.0.1");
+        assert_eq!(entry.local_port, 5432);
+        assert_eq!(entry.remote_address, "0.0.0.0");
+        assert_eq!(tcp_state(entry.state_code), ConnectionState::Listening);
+        assert_eq!(entry.details.inode, Some(41234));
+        assert_eq!(entry.details.uid, Some(999));
+        assert_eq!(entry.details.rx_queue, Some(3));
+        assert_eq!(entry.details.tx_queue, Some(0));
+        assert_eq!(entry.details.timer, None);
+    }
+
+    #[test]
+    fn test_parse_ipv6_with_timer() {
+        let line = "   1: 00000000000000000000000001000000:D431 00000000000000000000000001000000:1F90 01 000000A0:00000000 01:00000014 00000002  1000        0 55555 1 0000000000000000 20 4 30 10 -1";
+        let entry = parse_proc_net_line(line).unwrap();
+
+        assert_eq!(entry.local_address, "::1");
+        assert_eq!(entry.local_port, 54321);
+        assert_eq!(entry.remote_port, 8080);
+        assert_eq!(entry.details.tx_queue, Some(160));
+        assert_eq!(entry.details.retransmits, Some(2));
+        assert_eq!(entry.details.timer, Some(SocketTimer::Retransmit));
+        assert_eq!(entry.details.timer_expires_ms, Some(200));
+    }
+
+    #[test]
+    fn test_parse_unix_lines() {
+        let listener = parse_unix_line("0000000000000000: 00000002 00000000 00010000 0001 01 23456 /run/docker.sock").unwrap();
+        assert_eq!(listener.inode, 23456);
+        assert_eq!(listener.socket_type, UnixSocketType::Stream);
+        assert_eq!(listener.state, ConnectionState::Listening);
+        assert_eq!(listener.path, "/run/docker.sock");
+
+        let client = parse_unix_line("0000000000000000: 00000003 00000000 00000000 0005 03 23457").unwrap();
+        assert_eq!(client.socket_type, UnixSocketType::SeqPacket);
+        assert_eq!(client.state, ConnectionState::Established);
+        assert_eq!(client.path, "");
+
+        let abstract_dgram = parse_unix_line("0000000000000000: 00000002 00000000 00000000 0002 01 23458 @/tmp/.X11 lock").unwrap();
+        assert_eq!(abstract_dgram.state, ConnectionState::Listening);
+        assert_eq!(abstract_dgram.path, "@/tmp/.X11 lock");
+    }
+
+    #[test]
+    fn test_parse_sctp_lines() {
+        let endpoint = parse_sctp_endpoint_line(
+            "ffff8880062d6000 ffff888004e48000 2   10  29   36412 0    41577 10.0.0.1 192.168.1.5",
+        )
+        .unwrap();
+        assert_eq!(endpoint.local_address, "10.0.0.1");
+        assert_eq!(endpoint.local_port, 36412);
+        assert_eq!(endpoint.state, ConnectionState::Listening);
+        assert_eq!(endpoint.details.inode, Some(41577));
+
+        let assoc = parse_sctp_assoc_line(
+            "ffff888003a2c000 ffff888004e48800 0   10  3  51262    2        0        0     0 41580 36412 38412  10.0.0.1 *192.168.1.5 <-> *10.0.0.2 \t 30000     2    10   10    0    0        0        1        0   212992   212992",
+        )
+        .unwrap();
+        assert_eq!(assoc.local_address, "192.168.1.5");
+        assert_eq!(assoc.remote_address, "10.0.0.2");
+        assert_eq!(assoc.remote_port, 38412);
+        assert_eq!(assoc.state, ConnectionState::Established);
+        assert_eq!(assoc.details.inode, Some(41580));
+    }
+
+    #[test]
+    fn test_parse_namespace_link() {
+        assert_eq!(parse_namespace_link("net:[4026531840]"), Some(4026531840));
+        assert_eq!(parse_namespace_link("mnt:[4026531840]"), None);
+    }
+
+    #[test]
+    fn test_parse_socket_link() {
+        assert_eq!(parse_socket_link("socket:[41234]"), Some(41234));
+        assert_eq!(parse_socket_link("pipe:[41234]"), None);
+    }
+}
@@ -0,0 +1,397 @@
+use crate::platform::NetworkError;
+use std::collections::HashMap;
+use std::mem::size_of;
+
+/// `SOCK_DIAG_BY_FAMILY` request type (linux/sock_diag.h)
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+
+/// Ask for the peer inode of each Unix socket (linux/unix_diag.h)
+const UDIAG_SHOW_PEER: u32 = 0x04;
+
+/// Attribute carrying the peer inode in a unix_diag reply
+const UNIX_DIAG_PEER: u16 = 2;
+
+/// Ask inet_diag for `struct tcp_info` (linux/inet_diag.h, `1 << (INET_DIAG_INFO - 1)`)
+const INET_DIAG_INFO: u16 = 2;
+
+/// Byte offsets into `struct tcp_info` (linux/tcp.h); the struct only grows,
+/// so fields past the end of an older kernel's copy are simply absent
+const TCPI_RTT: usize = 68;
+const TCPI_SND_CWND: usize = 80;
+const TCPI_BYTES_ACKED: usize = 120;
+const TCPI_BYTES_RECEIVED: usize = 128;
+
+/// Netlink messages and attributes are padded to 4 bytes
+const NLMSG_ALIGNTO: usize = 4;
+
+/// Give up on a silent kernel after this long instead of blocking the collector
+const RECV_TIMEOUT_SECS: libc::time_t = 2;
+
+/// `struct unix_diag_req`
+#[repr(C)]
+struct UnixDiagReq {
+    sdiag_family: u8,
+    sdiag_protocol: u8,
+    pad: u16,
+    udiag_states: u32,
+    udiag_ino: u32,
+    udiag_show: u32,
+    udiag_cookie: [u32; 2],
+}
+
+/// `struct unix_diag_msg`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UnixDiagMsg {
+    udiag_family: u8,
+    udiag_type: u8,
+    udiag_state: u8,
+    pad: u8,
+    udiag_ino: u32,
+    udiag_cookie: [u32; 2],
+}
+
+/// `struct inet_diag_sockid`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InetDiagSockId {
+    idiag_sport: u16,
+    idiag_dport: u16,
+    idiag_src: [u32; 4],
+    idiag_dst: [u32; 4],
+    idiag_if: u32,
+    idiag_cookie: [u32; 2],
+}
+
+/// `struct inet_diag_req_v2`
+#[repr(C)]
+struct InetDiagReqV2 {
+    sdiag_family: u8,
+    sdiag_protocol: u8,
+    idiag_ext: u8,
+    pad: u8,
+    idiag_states: u32,
+    id: InetDiagSockId,
+}
+
+/// `struct inet_diag_msg`
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InetDiagMsg {
+    idiag_family: u8,
+    idiag_state: u8,
+    idiag_timer: u8,
+    idiag_retrans: u8,
+    id: InetDiagSockId,
+    idiag_expires: u32,
+    idiag_rqueue: u32,
+    idiag_wqueue: u32,
+    idiag_uid: u32,
+    idiag_inode: u32,
+}
+
+/// The parts of `struct tcp_info` the UI shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TcpInfo {
+    /// Smoothed round-trip time, in microseconds
+    pub rtt_us: u32,
+    /// Congestion window, in segments
+    pub cwnd: u32,
+    /// Bytes the peer acknowledged (Linux 4.1+)
+    pub bytes_acked: Option<u64>,
+    /// Bytes received in order (Linux 4.1+)
+    pub bytes_received: Option<u64>,
+}
+
+/// Read the fields we use from a `struct tcp_info` attribute
+pub fn parse_tcp_info(data: &[u8]) -> Option<TcpInfo> {
+    let field = |offset: usize| data.get(offset..).and_then(read_struct::<u32>);
+    let counter = |offset: usize| data.get(offset..).and_then(read_struct::<u64>);
+    Some(TcpInfo {
+        rtt_us: field(TCPI_RTT)?,
+        cwnd: field(TCPI_SND_CWND)?,
+        bytes_acked: counter(TCPI_BYTES_ACKED),
+        bytes_received: counter(TCPI_BYTES_RECEIVED),
+    })
+}
+
+/// Map each TCP socket inode (IPv4 and IPv6) to its `tcp_info`
+///
+/// Only covers the namespace the app runs in; sockets in other namespaces
+/// are missing from the map.
+pub fn tcp_info() -> Result<HashMap<u64, TcpInfo>, NetworkError> {
+    let mut infos = HashMap::new();
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        let request = InetDiagReqV2 {
+            sdiag_family: family as u8,
+            sdiag_protocol: libc::IPPROTO_TCP as u8,
+            idiag_ext: 1 << (INET_DIAG_INFO - 1),
+            pad: 0,
+            idiag_states: u32::MAX,
+            id: InetDiagSockId {
+                idiag_sport: 0,
+                idiag_dport: 0,
+                idiag_src: [0; 4],
+                idiag_dst: [0; 4],
+                idiag_if: 0,
+                idiag_cookie: [0; 2],
+            },
+        };
+        for payload in dump(as_bytes(&request))? {
+            let Some(msg) = read_struct::<InetDiagMsg>(&payload) else {
+                continue;
+            };
+            // Time-wait and request sockets have no inode and no tcp_info
+            if msg.idiag_inode == 0 {
+                continue;
+            }
+            for (kind, data) in parse_attributes(&payload[align(size_of::<InetDiagMsg>())..]) {
+                if kind == INET_DIAG_INFO {
+                    if let Some(info) = parse_tcp_info(data) {
+                        infos.insert(msg.idiag_inode as u64, info);
+                    }
+                }
+            }
+        }
+    }
+    Ok(infos)
+}
+
+/// Map each Unix socket inode to the inode of its connected peer
+pub fn unix_peers() -> Result<HashMap<u64, u64>, NetworkError> {
+    let request = UnixDiagReq {
+        sdiag_family: libc::AF_UNIX as u8,
+        sdiag_protocol: 0,
+        pad: 0,
+        udiag_states: u32::MAX,
+        udiag_ino: 0,
+        udiag_show: UDIAG_SHOW_PEER,
+        udiag_cookie: [0; 2],
+    };
+
+    let mut peers = HashMap::new();
+    for payload in dump(as_bytes(&request))? {
+        let Some(msg) = read_struct::<UnixDiagMsg>(&payload) else {
+            continue;
+        };
+        for (kind, data) in parse_attributes(&payload[align(size_of::<UnixDiagMsg>())..]) {
+            if kind == UNIX_DIAG_PEER {
+                if let Some(peer) = read_struct::<u32>(data) {
+                    peers.insert(msg.udiag_ino as u64, peer as u64);
+                }
+            }
+        }
+    }
+
+    Ok(peers)
+}
+
+/// Send one `SOCK_DIAG_BY_FAMILY` dump request and return every reply payload
+pub fn dump(request: &[u8]) -> Result<Vec<Vec<u8>>, NetworkError> {
+    let socket = NetlinkSocket::open()?;
+
+    let header = libc::nlmsghdr {
+        nlmsg_len: (size_of::<libc::nlmsghdr>() + request.len()) as u32,
+        nlmsg_type: SOCK_DIAG_BY_FAMILY,
+        nlmsg_flags: (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16,
+        nlmsg_seq: 1,
+        nlmsg_pid: 0,
+    };
+    let mut message = as_bytes(&header).to_vec();
+    message.extend_from_slice(request);
+    socket.send(&message)?;
+
+    let mut payloads = Vec::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let received = socket.recv(&mut buffer)?;
+        let mut offset = 0;
+        while offset + size_of::<libc::nlmsghdr>() <= received {
+            let header = read_struct::<libc::nlmsghdr>(&buffer[offset..received])
+                .ok_or_else(|| NetworkError::ParseError("Truncated netlink header".to_string()))?;
+            let length = header.nlmsg_len as usize;
+            if length < size_of::<libc::nlmsghdr>() || offset + length > received {
+                return Err(NetworkError::ParseError("Malformed netlink message".to_string()));
+            }
+
+            match header.nlmsg_type as libc::c_int {
+                libc::NLMSG_DONE => return Ok(payloads),
+                libc::NLMSG_ERROR => {
+                    let code = read_struct::<i32>(&buffer[offset + size_of::<libc::nlmsghdr>()..offset + length])
+                        .unwrap_or(0);
+                    return Err(classify(
+                        "sock_diag request failed",
+                        std::io::Error::from_raw_os_error(-code),
+                    ));
+                }
+                _ => payloads.push(buffer[offset + size_of::<libc::nlmsghdr>()..offset + length].to_vec()),
+            }
+
+            offset += align(length);
+        }
+    }
+}
+
+/// Split a run of `struct rtattr` records into `(type, data)` pairs
+pub fn parse_attributes(mut data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+    while data.len() >= 4 {
+        let length = u16::from_ne_bytes([data[0], data[1]]) as usize;
+        let kind = u16::from_ne_bytes([data[2], data[3]]);
+        if length < 4 || length > data.len() {
+            break;
+        }
+        attributes.push((kind, &data[4..length]));
+        data = &data[align(length).min(data.len())..];
+    }
+    attributes
+}
+
+/// Read a plain-old-data struct from the start of a byte slice
+pub fn read_struct<T: Copy>(data: &[u8]) -> Option<T> {
+    if data.len() < size_of::<T>() {
+        return None;
+    }
+    // SAFETY: length checked above; read_unaligned tolerates any alignment
+    Some(unsafe { std::ptr::read_unaligned(data.as_ptr() as *const T) })
+}
+
+/// View a `#[repr(C)]` request struct as bytes
+pub fn as_bytes<T>(value: &T) -> &[u8] {
+    // SAFETY: only used with padding-free #[repr(C)] kernel structs
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+pub fn align(length: usize) -> usize {
+    (length + NLMSG_ALIGNTO - 1) & !(NLMSG_ALIGNTO - 1)
+}
+
+/// Owned `NETLINK_SOCK_DIAG` socket, closed on drop
+struct NetlinkSocket(libc::c_int);
+
+impl NetlinkSocket {
+    fn open() -> Result<Self, NetworkError> {
+        // SAFETY: plain socket(2) call; the descriptor is owned by the returned value
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_SOCK_DIAG)
+        };
+        if fd < 0 {
+            return Err(last_error("Failed to open sock_diag socket"));
+        }
+        let socket = Self(fd);
+
+        let timeout = libc::timeval { tv_sec: RECV_TIMEOUT_SECS, tv_usec: 0 };
+        // SAFETY: valid descriptor and a correctly sized timeval
+        let result = unsafe {
+            libc::setsockopt(
+                socket.0,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(last_error("Failed to set sock_diag timeout"));
+        }
+
+        Ok(socket)
+    }
+
+    fn send(&self, message: &[u8]) -> Result<(), NetworkError> {
+        // SAFETY: zeroed sockaddr_nl addresses the kernel (pid 0)
+        let mut kernel: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        kernel.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        // SAFETY: message and address are valid for the duration of the call
+        let sent = unsafe {
+            libc::sendto(
+                self.0,
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+                &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+                size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            return Err(last_error("Failed to send sock_diag request"));
+        }
+        Ok(())
+    }
+
+    fn recv(&self, buffer: &mut [u8]) -> Result<usize, NetworkError> {
+        // SAFETY: buffer is valid and writable for its full length
+        let received = unsafe { libc::recv(self.0, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len(), 0) };
+        if received < 0 {
+            let error = std::io::Error::last_os_error();
+            return Err(match error.kind() {
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => {
+                    NetworkError::Timeout("sock_diag reply did not arrive in time".to_string())
+                }
+                _ => NetworkError::SystemCallFailed(format!("Failed to read sock_diag reply: {}", error)),
+            });
+        }
+        Ok(received as usize)
+    }
+}
+
+impl Drop for NetlinkSocket {
+    fn drop(&mut self) {
+        // SAFETY: we own the descriptor
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+fn last_error(context: &str) -> NetworkError {
+    classify(context, std::io::Error::last_os_error())
+}
+
+/// Errors that will not go away on retry map to `PermissionDenied` or
+/// `UnsupportedPlatform` (no netlink family, or no inet_diag/unix_diag module)
+fn classify(context: &str, error: std::io::Error) -> NetworkError {
+    match error.raw_os_error() {
+        Some(libc::EPERM) | Some(libc::EACCES) => NetworkError::PermissionDenied(format!("{}: {}", context, error)),
+        Some(libc::EAFNOSUPPORT) | Some(libc::EPROTONOSUPPORT) | Some(libc::ENOENT) | Some(libc::EOPNOTSUPP) => {
+            NetworkError::UnsupportedPlatform(format!("{}: {}", context, error))
+        }
+        _ => NetworkError::SystemCallFailed(format!("{}: {}", context, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_attributes() {
+        // Two attributes: type 2 with a u32 payload, then type 7 with 1 byte padded to 8
+        let mut data = Vec::new();
+        data.extend_from_slice(&8u16.to_ne_bytes());
+        data.extend_from_slice(&2u16.to_ne_bytes());
+        data.extend_from_slice(&4242u32.to_ne_bytes());
+        data.extend_from_slice(&5u16.to_ne_bytes());
+        data.extend_from_slice(&7u16.to_ne_bytes());
+        data.extend_from_slice(&[9, 0, 0, 0]);
+
+        let attributes = parse_attributes(&data);
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes[0].0, 2);
+        assert_eq!(read_struct::<u32>(attributes[0].1), Some(4242));
+        assert_eq!(attributes[1], (7, &[9u8][..]));
+
+        let mut info = vec![0u8; TCPI_BYTES_ACKED];
+        info[TCPI_RTT..TCPI_RTT + 4].copy_from_slice(&1500u32.to_ne_bytes());
+        info[TCPI_SND_CWND..TCPI_SND_CWND + 4].copy_from_slice(&10u32.to_ne_bytes());
+        // A pre-4.1 kernel's tcp_info ends before the byte counters
+        assert_eq!(
+            parse_tcp_info(&info),
+            Some(TcpInfo { rtt_us: 1500, cwnd: 10, bytes_acked: None, bytes_received: None })
+        );
+        info.extend_from_slice(&123_456u64.to_ne_bytes());
+        info.extend_from_slice(&789u64.to_ne_bytes());
+        assert_eq!(parse_tcp_info(&info).unwrap().bytes_received, Some(789));
+        assert_eq!(parse_tcp_info(&info[..TCPI_SND_CWND]), None);
+    }
+}
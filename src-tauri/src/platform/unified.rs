@@ -1,40 +1,262 @@
 use crate::models::{ConnectionInfo, Protocol, ConnectionState};
 use crate::platform::{NetworkProvider, NetworkError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use sysinfo::System;
 
+/// Concrete backends the unified provider can delegate to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    /// Win32 IP Helper tables
+    Windows,
+    /// Kernel socket tables in /proc/net
+    LinuxProcfs,
+    /// lsof on Linux
+    LinuxLsof,
+    /// lsof on macOS
+    MacosLsof,
+    /// sysinfo process heuristics (last resort)
+    Sysinfo,
+}
+
+impl BackendKind {
+    /// Backends compiled for the current platform, in automatic fallback order
+    pub fn available() -> Vec<BackendKind> {
+        let mut backends = Vec::new();
+        if cfg!(target_os = "windows") {
+            backends.push(BackendKind::Windows);
+        }
+        if cfg!(target_os = "linux") {
+            backends.push(BackendKind::LinuxProcfs);
+            backends.push(BackendKind::LinuxLsof);
+        }
+        if cfg!(target_os = "macos") {
+            backends.push(BackendKind::MacosLsof);
+        }
+        backends.push(BackendKind::Sysinfo);
+        backends
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            BackendKind::Windows => "Windows IP Helper",
+            BackendKind::LinuxProcfs => "Linux /proc/net",
+            BackendKind::LinuxLsof => "Linux lsof",
+            BackendKind::MacosLsof => "macOS lsof",
+            BackendKind::Sysinfo => "sysinfo fallback",
+        }
+    }
+
+    fn unavailable_error(&self) -> NetworkError {
+        NetworkError::UnsupportedPlatform(format!(
+            "{} backend is not available on {}",
+            self.display_name(),
+            std::env::consts::OS
+        ))
+    }
+}
+
+/// Outcome of the most recent run of a single backend
+#[derive(Debug, Clone, Default)]
+struct BackendStats {
+    last_run_at: Option<DateTime<Utc>>,
+    last_duration_ms: Option<u64>,
+    last_error: Option<String>,
+    last_row_count: Option<usize>,
+}
+
+/// Diagnostics for one backend, as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendDiagnostics {
+    pub backend: BackendKind,
+    pub name: &'static str,
+    pub supported: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_row_count: Option<usize>,
+}
+
+/// Snapshot of backend selection state
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    /// Backend forced by the user, `None` for the automatic chain
+    pub forced_backend: Option<BackendKind>,
+    /// Backend whose rows were returned by the latest collection
+    pub active_backend: Option<BackendKind>,
+    /// Whether backends that support it read every network namespace
+    pub all_namespaces: bool,
+    pub backends: Vec<BackendDiagnostics>,
+}
+
 /// Unified cross-platform network provider
 /// 
-/// This implementation delegates to platform-specific optimized implementations
-pub struct UnifiedNetworkProvider;
+/// This implementation delegates to platform-specific optimized implementations,
+/// falling back along `BackendKind::available()` unless a backend is forced.
+/// Every backend run is timed and recorded so `diagnostics()` can explain
+/// where the data came from.
+pub struct UnifiedNetworkProvider {
+    forced_backend: Mutex<Option<BackendKind>>,
+    active_backend: Mutex<Option<BackendKind>>,
+    stats: Mutex<HashMap<BackendKind, BackendStats>>,
+    all_namespaces: AtomicBool,
+    /// Providers standing in for built-in backends (tests)
+    overrides: HashMap<BackendKind, Arc<dyn NetworkProvider + Send + Sync>>,
+}
 
 impl UnifiedNetworkProvider {
     pub fn new() -> Self {
-        Self
+        Self {
+            forced_backend: Mutex::new(None),
+            active_backend: Mutex::new(None),
+            stats: Mutex::new(HashMap::new()),
+            all_namespaces: AtomicBool::new(false),
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Serve `kind` from `provider` instead of the built-in backend
+    #[cfg(test)]
+    pub fn with_backend(mut self, kind: BackendKind, provider: Arc<dyn NetworkProvider + Send + Sync>) -> Self {
+        self.overrides.insert(kind, provider);
+        self
+    }
+
+    /// Force a specific backend, or `None` to restore the automatic chain
+    pub fn set_forced_backend(&self, backend: Option<BackendKind>) -> Result<(), NetworkError> {
+        if let Some(kind) = backend {
+            if !BackendKind::available().contains(&kind) {
+                return Err(kind.unavailable_error());
+            }
+        }
+        *self.forced_backend.lock().unwrap() = backend;
+        Ok(())
+    }
+
+    pub fn forced_backend(&self) -> Option<BackendKind> {
+        *self.forced_backend.lock().unwrap()
+    }
+
+    /// Also collect sockets from other network namespaces (containers);
+    /// only the Linux /proc/net backend can do this, others ignore it
+    pub fn set_all_namespaces(&self, enabled: bool) {
+        self.all_namespaces.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn all_namespaces(&self) -> bool {
+        self.all_namespaces.load(Ordering::Relaxed)
+    }
+
+    /// Report support status and last-run statistics for every available backend
+    pub fn diagnostics(&self) -> DiagnosticsReport {
+        let stats = self.stats.lock().unwrap().clone();
+        let backends = BackendKind::available()
+            .into_iter()
+            .map(|kind| {
+                let entry = stats.get(&kind).cloned().unwrap_or_default();
+                BackendDiagnostics {
+                    backend: kind,
+                    name: kind.display_name(),
+                    supported: self.backend_supported(kind),
+                    last_run_at: entry.last_run_at,
+                    last_duration_ms: entry.last_duration_ms,
+                    last_error: entry.last_error,
+                    last_row_count: entry.last_row_count,
+                }
+            })
+            .collect();
+
+        DiagnosticsReport {
+            forced_backend: self.forced_backend(),
+            active_backend: *self.active_backend.lock().unwrap(),
+            all_namespaces: self.all_namespaces(),
+            backends,
+        }
+    }
+
+    fn backend_supported(&self, kind: BackendKind) -> bool {
+        if let Some(provider) = self.overrides.get(&kind) {
+            return provider.is_supported();
+        }
+        match kind {
+            #[cfg(target_os = "windows")]
+            BackendKind::Windows => crate::platform::windows::WindowsNetworkProvider::new().is_supported(),
+            #[cfg(target_os = "linux")]
+            BackendKind::LinuxProcfs => crate::platform::procfs::ProcfsNetworkProvider::new().is_supported(),
+            #[cfg(target_os = "linux")]
+            BackendKind::LinuxLsof => crate::platform::linux::LinuxNetworkProvider::new().is_supported(),
+            #[cfg(target_os = "macos")]
+            BackendKind::MacosLsof => crate::platform::macos::MacOSNetworkProvider::new().is_supported(),
+            BackendKind::Sysinfo => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Run one backend, recording its duration, row count and error
+    fn run_backend(&self, kind: BackendKind) -> Result<Vec<ConnectionInfo>, NetworkError> {
+        let started = Instant::now();
+        let result = match kind {
+            _ if self.overrides.contains_key(&kind) => self.overrides[&kind].get_all_connections(),
+            #[cfg(target_os = "windows")]
+            BackendKind::Windows => crate::platform::windows::WindowsNetworkProvider::new().get_all_connections(),
+            #[cfg(target_os = "linux")]
+            BackendKind::LinuxProcfs => crate::platform::procfs::ProcfsNetworkProvider::new()
+                .with_all_namespaces(self.all_namespaces())
+                .get_all_connections(),
+            #[cfg(target_os = "linux")]
+            BackendKind::LinuxLsof => crate::platform::linux::LinuxNetworkProvider::new().get_all_connections(),
+            #[cfg(target_os = "macos")]
+            BackendKind::MacosLsof => crate::platform::macos::MacOSNetworkProvider::new().get_all_connections(),
+            BackendKind::Sysinfo => self.get_connections_via_sysinfo(),
+            #[allow(unreachable_patterns)]
+            _ => Err(kind.unavailable_error()),
+        };
+
+        let stats = BackendStats {
+            last_run_at: Some(Utc::now()),
+            last_duration_ms: Some(started.elapsed().as_millis() as u64),
+            last_error: result.as_ref().err().map(|e| e.to_string()),
+            last_row_count: result.as_ref().ok().map(|rows| rows.len()),
+        };
+        self.stats.lock().unwrap().insert(kind, stats);
+
+        if result.is_ok() {
+            *self.active_backend.lock().unwrap() = Some(kind);
+        }
+        result
     }
 
     /// Get connections using platform-specific methods with sysinfo fallback
     fn get_connections_with_fallback(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
-        // Try platform-specific optimized implementations first
-        #[cfg(target_os = "windows")]
-        {
-            match crate::platform::windows::WindowsNetworkProvider::new().get_all_connections() {
-                Ok(connections) if !connections.is_empty() => return Ok(connections),
-                Ok(_) => println!("Windows provider returned empty, trying sysinfo fallback"),
-                Err(e) => println!("Windows provider failed: {:?}, trying sysinfo fallback", e),
+        // A forced backend gets no fallback, so its errors surface as-is;
+        // nothing is active while it fails
+        if let Some(kind) = self.forced_backend() {
+            let result = self.run_backend(kind);
+            if result.is_err() {
+                *self.active_backend.lock().unwrap() = None;
             }
+            return result;
         }
 
-        #[cfg(target_os = "linux")]
-        {
-            match crate::platform::linux::LinuxNetworkProvider::new().get_all_connections() {
+        // Try platform-specific optimized implementations first, sysinfo last
+        let chain = BackendKind::available();
+        let (fallback, preferred) = chain.split_last().expect("sysinfo backend is always available");
+
+        for &kind in preferred {
+            match self.run_backend(kind) {
                 Ok(connections) if !connections.is_empty() => return Ok(connections),
-                Ok(_) => println!("Linux provider returned empty, trying sysinfo fallback"),
-                Err(e) => println!("Linux provider failed: {:?}, trying sysinfo fallback", e),
+                Ok(_) => println!("{} provider returned empty, trying next backend", kind.display_name()),
+                Err(e) => println!("{} provider failed: {:?}, trying next backend", kind.display_name(), e),
             }
         }
 
-        // Fallback to sysinfo for all platforms
-        self.get_connections_via_sysinfo()
+        self.run_backend(*fallback)
     }
 
     /// Get connections using sysinfo as universal fallback
@@ -138,3 +360,94 @@ impl NetworkProvider for UnifiedNetworkProvider {
         cfg!(any(target_os = "windows", target_os = "linux", target_os = "macos", target_os = "freebsd"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Backend that always returns the same result
+    struct StubProvider(Result<Vec<ConnectionInfo>, NetworkError>);
+
+    impl NetworkProvider for StubProvider {
+        fn get_all_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+            self.0.clone()
+        }
+
+        fn get_tcp_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+            self.0.clone()
+        }
+
+        fn get_udp_connections(&self) -> Result<Vec<ConnectionInfo>, NetworkError> {
+            Ok(Vec::new())
+        }
+
+        fn platform_name(&self) -> &'static str {
+            "stub"
+        }
+
+        fn is_supported(&self) -> bool {
+            true
+        }
+    }
+
+    fn rows(process: &str) -> Vec<ConnectionInfo> {
+        vec![ConnectionInfo::new(Protocol::TCP, "127.0.0.1".to_string(), 8080, "0.0.0.0".to_string(), 0, ConnectionState::Listen, 1, process.to_string())]
+    }
+
+    /// Every available backend fails except sysinfo, which returns one row
+    fn provider() -> UnifiedNetworkProvider {
+        BackendKind::available().into_iter().fold(UnifiedNetworkProvider::new(), |provider, kind| {
+            let result = match kind {
+                BackendKind::Sysinfo => Ok(rows("fallback")),
+                _ => Err(NetworkError::CommandFailed(format!("{} is down", kind.display_name()))),
+            };
+            provider.with_backend(kind, Arc::new(StubProvider(result)))
+        })
+    }
+
+    #[test]
+    fn test_fallback_chain_records_diagnostics() {
+        let provider = provider();
+        assert_eq!(provider.get_all_connections().unwrap()[0].process_name, "fallback");
+
+        let report = provider.diagnostics();
+        assert_eq!(report.forced_backend, None);
+        assert_eq!(report.active_backend, Some(BackendKind::Sysinfo));
+        for backend in &report.backends {
+            assert!(backend.supported);
+            assert!(backend.last_run_at.is_some());
+            if backend.backend == BackendKind::Sysinfo {
+                assert_eq!((backend.last_row_count, backend.last_error.as_deref()), (Some(1), None));
+            } else {
+                assert!(backend.last_error.as_deref().unwrap().contains("is down"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_forced_backend_gets_no_fallback() {
+        let preferred = BackendKind::available()[0];
+        let provider = provider();
+        provider.set_forced_backend(Some(preferred)).unwrap();
+        assert!(matches!(provider.get_all_connections(), Err(NetworkError::CommandFailed(_))));
+
+        let report = provider.diagnostics();
+        assert_eq!(report.forced_backend, Some(preferred));
+        assert_eq!(report.active_backend, None);
+        let sysinfo = report.backends.iter().find(|b| b.backend == BackendKind::Sysinfo).unwrap();
+        assert!(sysinfo.last_run_at.is_none());
+
+        provider.set_forced_backend(Some(BackendKind::Sysinfo)).unwrap();
+        assert_eq!(provider.get_all_connections().unwrap()[0].process_name, "fallback");
+        assert_eq!(provider.diagnostics().active_backend, Some(BackendKind::Sysinfo));
+
+        provider.set_forced_backend(Some(preferred)).unwrap();
+        assert!(provider.get_all_connections().is_err());
+        assert_eq!(provider.diagnostics().active_backend, None);
+        provider.set_forced_backend(Some(BackendKind::Sysinfo)).unwrap();
+
+        let foreign = if cfg!(target_os = "windows") { BackendKind::MacosLsof } else { BackendKind::Windows };
+        assert!(provider.set_forced_backend(Some(foreign)).is_err());
+        assert_eq!(provider.forced_backend(), Some(BackendKind::Sysinfo));
+    }
+}
@@ -0,0 +1,438 @@
+use crate::filter::filter_connections;
+use crate::models::{ConnectionInfo, ConnectionState, Protocol};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, TcpListener, UdpSocket};
+
+/// Upper bound for one free-port query
+pub const MAX_FREE_PORTS: usize = 1000;
+
+/// Inclusive port range, e.g. `{ "start": 3000, "end": 3999 }`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+/// Errors from port queries
+#[derive(Debug, Clone)]
+pub enum PortError {
+    /// Bad protocol, address, range or count
+    Invalid(String),
+    /// The address does not belong to this host, so nothing can bind it
+    AddressNotLocal(String),
+}
+
+impl fmt::Display for PortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortError::Invalid(msg) => write!(f, "Invalid port query: {}", msg),
+            PortError::AddressNotLocal(address) => write!(f, "{} is not an address of this host", address),
+        }
+    }
+}
+
+impl std::error::Error for PortError {}
+
+/// Parse an address as providers report it: `*` and empty mean "any", brackets are optional
+///
+/// The wildcard maps to `::`, which `addresses_overlap` treats as covering both families.
+pub fn parse_bind_address(address: &str) -> Option<IpAddr> {
+    let address = address.trim().trim_start_matches('[').trim_end_matches(']');
+    if address.is_empty() || address == "*" {
+        return Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+    }
+    address.parse::<IpAddr>().ok().map(|ip| ip.to_canonical())
+}
+
+/// Whether sockets bound to `a` and `b` on the same port would collide
+///
+/// A wildcard collides with every address of its family, and `::` also with
+/// IPv4 because Linux, macOS and Windows all bind dual-stack by default.
+pub fn addresses_overlap(a: IpAddr, b: IpAddr) -> bool {
+    let (a, b) = (a.to_canonical(), b.to_canonical());
+    if a == b {
+        return true;
+    }
+    match (a, b) {
+        (IpAddr::V6(v6), _) | (_, IpAddr::V6(v6)) if v6.is_unspecified() => true,
+        (IpAddr::V4(x), IpAddr::V4(y)) => x.is_unspecified() || y.is_unspecified(),
+        _ => false,
+    }
+}
+
+/// Whether `conn` already holds `protocol` port space on an address overlapping `address`
+///
+/// TIME_WAIT leftovers are ignored: listeners bind with SO_REUSEADDR (std
+/// does on Unix), which lets them reuse such ports. Sockets in other network
+/// namespaces live in a separate port space and never collide.
+fn holds_port(conn: &ConnectionInfo, protocol: &Protocol, address: IpAddr) -> bool {
+    conn.protocol.same_kind(protocol)
+        && conn.state != ConnectionState::TimeWait
+        && !conn.context.foreign_namespace
+        && parse_bind_address(&conn.local_address).is_some_and(|local| addresses_overlap(local, address))
+}
+
+/// The first `count` ports in `range` free for `protocol` on `address`
+///
+/// A port must be absent from the snapshot and survive a real `bind()`; the
+/// snapshot catches sockets the bind would miss (e.g. SO_REUSEPORT
+/// listeners), the bind catches anything the backend could not see. The
+/// result is advisory: another process may still take a port before the
+/// caller binds it.
+pub fn find_free_ports(
+    connections: &[ConnectionInfo],
+    protocol: &Protocol,
+    address: IpAddr,
+    range: PortRange,
+    count: usize,
+) -> Result<Vec<u16>, PortError> {
+    if !matches!(protocol, Protocol::TCP | Protocol::UDP) {
+        return Err(PortError::Invalid(format!("only TCP and UDP ports can be probed, not {}", protocol)));
+    }
+    if range.start == 0 || range.start > range.end {
+        return Err(PortError::Invalid(format!("invalid port range {}-{}", range.start, range.end)));
+    }
+    if count == 0 || count > MAX_FREE_PORTS {
+        return Err(PortError::Invalid(format!("count must be 1-{}", MAX_FREE_PORTS)));
+    }
+
+    let taken: HashSet<u16> = connections
+        .iter()
+        .filter(|conn| holds_port(conn, protocol, address))
+        .map(|conn| conn.local_port)
+        .collect();
+
+    let mut free = Vec::new();
+    for port in (range.start..=range.end).filter(|port| !taken.contains(port)) {
+        match try_bind(protocol, SocketAddr::new(address, port)) {
+            Ok(()) => free.push(port),
+            // Not checked: the snapshot alone has to do
+            Err(e) if family_unavailable(address, &e) => free.push(port),
+            Err(e) if e.kind() == ErrorKind::AddrNotAvailable => {
+                return Err(PortError::AddressNotLocal(address.to_string()));
+            }
+            // In use, or privileged and we are not
+            Err(_) => continue,
+        }
+        if free.len() == count {
+            break;
+        }
+    }
+    Ok(free)
+}
+
+/// `EAFNOSUPPORT`; Winsock reports it as `WSAEAFNOSUPPORT`
+#[cfg(unix)]
+const EAFNOSUPPORT: i32 = libc::EAFNOSUPPORT;
+#[cfg(windows)]
+const EAFNOSUPPORT: i32 = 10047;
+
+/// Whether a bind failed because the host has IPv6 disabled rather than because of the port
+///
+/// Binding `::` then fails with EAFNOSUPPORT or EADDRNOTAVAIL even though
+/// `::` is local everywhere IPv6 exists.
+fn family_unavailable(address: IpAddr, error: &std::io::Error) -> bool {
+    address.is_ipv6()
+        && (error.raw_os_error() == Some(EAFNOSUPPORT)
+            || (address.is_unspecified() && error.kind() == ErrorKind::AddrNotAvailable))
+}
+
+/// Bind and immediately release; the socket closes when dropped
+fn try_bind(protocol: &Protocol, address: SocketAddr) -> std::io::Result<()> {
+    match protocol {
+        Protocol::UDP => UdpSocket::bind(address).map(drop),
+        _ => TcpListener::bind(address).map(drop),
+    }
+}
+
+/// One socket on the queried port and whether it stands in the way of a new bind
+#[derive(Debug, Clone, Serialize)]
+pub struct PortUser {
+    pub protocol: String,
+    pub local_address: String,
+    pub remote_address: String,
+    pub remote_port: u16,
+    pub state: String,
+    pub pid: u32,
+    pub process_name: String,
+    pub blocks_bind: bool,
+    /// Why it does or does not block, in one sentence
+    pub reason: String,
+}
+
+/// A process holding a blocking socket, with how to get rid of it
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessToStop {
+    pub pid: u32,
+    pub process_name: String,
+    pub hint: String,
+}
+
+/// Everything on one port, answering "why does bind fail with EADDRINUSE?"
+#[derive(Debug, Clone, Serialize)]
+pub struct PortUsageReport {
+    pub protocol: String,
+    pub address: String,
+    pub port: u16,
+    pub reuse_addr: bool,
+    pub sockets: Vec<PortUser>,
+    pub blocked: bool,
+    pub processes_to_stop: Vec<ProcessToStop>,
+    /// Error from a real bind attempt, when one was made and failed
+    pub bind_error: Option<String>,
+    pub summary: String,
+}
+
+/// Decide whether `conn`, already known to use `port` locally, blocks binding `address`
+///
+/// Follows Linux semantics, which macOS and Windows match for these cases:
+/// two sockets collide when their addresses overlap (see `addresses_overlap`);
+/// SO_REUSEADDR only lets a TCP listener bind over connections that are not
+/// themselves listening, TIME_WAIT included.
+fn explain_socket(conn: &ConnectionInfo, address: IpAddr, reuse_addr: bool) -> (bool, String) {
+    if conn.context.foreign_namespace {
+        return (false, "lives in another network namespace, which has its own ports".to_string());
+    }
+    let Some(local) = parse_bind_address(&conn.local_address) else {
+        return (false, format!("bound to unrecognised address '{}'", conn.local_address));
+    };
+    if !addresses_overlap(local, address) {
+        return (
+            false,
+            format!("bound to {} only, which does not overlap {}", conn.local_address, address),
+        );
+    }
+    let scope = if local.is_unspecified() {
+        format!("the wildcard {}, which covers every local address", conn.local_address)
+    } else if address.is_unspecified() {
+        format!("{}, which a wildcard bind would cover", conn.local_address)
+    } else {
+        conn.local_address.clone()
+    };
+
+    if conn.state.is_listening() {
+        return match conn.protocol {
+            Protocol::UDP => (true, format!("UDP socket bound to {}", scope)),
+            _ => (true, format!("listening on {}", scope)),
+        };
+    }
+    if conn.state == ConnectionState::TimeWait {
+        return if reuse_addr {
+            (false, "TIME_WAIT leftover; SO_REUSEADDR lets a new listener bind over it".to_string())
+        } else {
+            (true, "TIME_WAIT leftover; without SO_REUSEADDR the bind fails until it expires (up to 60s)".to_string())
+        };
+    }
+    if conn.protocol == Protocol::UDP {
+        return (true, format!("UDP socket bound to {}", scope));
+    }
+    if reuse_addr {
+        (false, format!("{} connection; SO_REUSEADDR allows a listener alongside it", conn.state))
+    } else {
+        (true, format!("{} connection on {}; blocks a bind without SO_REUSEADDR", conn.state, scope))
+    }
+}
+
+fn stop_hint(conn: &ConnectionInfo) -> String {
+    if conn.pid == 0 {
+        return "owner not visible; run as root/administrator to see it".to_string();
+    }
+    if let Some(unit) = &conn.context.systemd_unit {
+        if unit.ends_with(".socket") {
+            return format!("socket-activated by {}; stop the socket unit, stopping the service is not enough", unit);
+        }
+        if unit.ends_with(".service") {
+            return format!("managed by {}; stop the unit or it may be restarted", unit);
+        }
+    }
+    if let Some(container) = &conn.context.container_id {
+        let short = &container[..container.len().min(12)];
+        return format!("runs in {} container {}; stop the container", conn.context.container_runtime.as_deref().unwrap_or("a"), short);
+    }
+    if let Some(forward) = &conn.forward {
+        return format!("{} forwarding to {}; stop the forward", forward.forwarder, forward.host);
+    }
+    format!("stop {} (pid {})", conn.process_name, conn.pid)
+}
+
+/// Every socket using `port` locally, and which of them block binding `address`
+///
+/// `reuse_addr` describes the program that wants the port; most servers set
+/// SO_REUSEADDR, and std does on Unix. The snapshot is narrowed with the same
+/// protocol/port filter the table uses, then to exact local-port matches.
+pub fn explain_port_usage(
+    connections: &[ConnectionInfo],
+    protocol: &Protocol,
+    address: IpAddr,
+    port: u16,
+    reuse_addr: bool,
+) -> PortUsageReport {
+    let candidates = filter_connections(connections, &protocol.to_string(), Some(port));
+    let mut sockets = Vec::new();
+    let mut processes_to_stop: Vec<ProcessToStop> = Vec::new();
+
+    for conn in candidates.iter().filter(|conn| conn.local_port == port && conn.protocol.same_kind(protocol)) {
+        let (blocks_bind, reason) = explain_socket(conn, address, reuse_addr);
+        if blocks_bind && !processes_to_stop.iter().any(|p| p.pid == conn.pid && p.process_name == conn.process_name) {
+            processes_to_stop.push(ProcessToStop {
+                pid: conn.pid,
+                process_name: conn.process_name.clone(),
+                hint: stop_hint(conn),
+            });
+        }
+        sockets.push(PortUser {
+            protocol: conn.protocol.to_string(),
+            local_address: conn.local_address.clone(),
+            remote_address: conn.remote_address.clone(),
+            remote_port: conn.remote_port,
+            state: conn.state.to_string(),
+            pid: conn.pid,
+            process_name: conn.process_name.clone(),
+            blocks_bind,
+            reason,
+        });
+    }
+    // Blocking sockets first, listeners before connections
+    sockets.sort_by_key(|socket| (!socket.blocks_bind, !socket.state.starts_with("LISTEN")));
+
+    let blocked = sockets.iter().any(|socket| socket.blocks_bind);
+    let target = format!("{} port {} on {}", protocol, port, address);
+    let summary = if sockets.is_empty() {
+        format!("Nothing uses {}.", target)
+    } else if blocked {
+        let owners: Vec<String> = processes_to_stop
+            .iter()
+            .map(|p| format!("{} (pid {})", p.process_name, p.pid))
+            .collect();
+        format!("{} is in use by {}. Stop it to free the port.", target, owners.join(", "))
+    } else {
+        format!(
+            "{} can be bound; {} remaining socket(s) do not conflict.",
+            target,
+            sockets.len()
+        )
+    };
+
+    PortUsageReport {
+        protocol: protocol.to_string(),
+        address: address.to_string(),
+        port,
+        reuse_addr,
+        sockets,
+        blocked,
+        processes_to_stop,
+        bind_error: None,
+        summary,
+    }
+}
+
+/// Confirm the report with a real bind, which also sees sockets the backend missed
+pub fn confirm_with_bind(report: &mut PortUsageReport, protocol: &Protocol, address: IpAddr) {
+    if !matches!(protocol, Protocol::TCP | Protocol::UDP) || !report.reuse_addr {
+        // std always sets SO_REUSEADDR on Unix, so it cannot test the other case
+        return;
+    }
+    if let Err(e) = try_bind(protocol, SocketAddr::new(address, report.port)) {
+        if family_unavailable(address, &e) {
+            // No IPv6 on this host, so the bind tells nothing about the port
+            return;
+        }
+        report.bind_error = Some(e.to_string());
+        if !report.blocked && e.kind() == ErrorKind::AddrInUse {
+            report.blocked = true;
+            report.summary = format!(
+                "{} port {} on {} is in use, but its owner is not visible; run as root/administrator to see it.",
+                protocol, report.port, address
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(address: &str) -> IpAddr {
+        parse_bind_address(address).unwrap()
+    }
+
+    #[test]
+    fn test_addresses_overlap() {
+        assert!(addresses_overlap(ip("0.0.0.0"), ip("127.0.0.1")));
+        assert!(addresses_overlap(ip("*"), ip("192.168.1.5")));
+        assert!(addresses_overlap(ip("[::]"), ip("127.0.0.1")));
+        assert!(addresses_overlap(ip("::ffff:127.0.0.1"), ip("127.0.0.1")));
+        assert!(!addresses_overlap(ip("127.0.0.1"), ip("127.0.0.2")));
+        assert!(!addresses_overlap(ip("0.0.0.0"), ip("::1")));
+        assert!(!addresses_overlap(ip("::1"), ip("127.0.0.1")));
+    }
+
+    #[test]
+    fn test_ipv6_disabled_is_not_a_conflict() {
+        let unsupported = std::io::Error::from_raw_os_error(EAFNOSUPPORT);
+        let not_available = std::io::Error::from(ErrorKind::AddrNotAvailable);
+        assert!(family_unavailable(ip("*"), &unsupported));
+        assert!(family_unavailable(ip("::"), &not_available));
+        assert!(!family_unavailable(ip("2001:db8::1"), &not_available));
+        assert!(!family_unavailable(ip("0.0.0.0"), &unsupported));
+    }
+
+    #[test]
+    fn test_find_free_ports_checks_snapshot_and_bind() {
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+        let held = TcpListener::bind((loopback, 0)).unwrap();
+        let held_port = held.local_addr().unwrap().port();
+        let range = PortRange { start: held_port, end: held_port };
+
+        // Invisible to the snapshot, but the bind still catches it
+        assert_eq!(find_free_ports(&[], &Protocol::TCP, loopback, range, 1).unwrap(), Vec::<u16>::new());
+        drop(held);
+        assert_eq!(find_free_ports(&[], &Protocol::TCP, loopback, range, 1).unwrap(), vec![held_port]);
+
+        // A wildcard listener in the snapshot blocks it without binding
+        let listener = ConnectionInfo::new(
+            Protocol::TCP,
+            "0.0.0.0".to_string(),
+            held_port,
+            "*".to_string(),
+            0,
+            ConnectionState::Listening,
+            1,
+            "nginx".to_string(),
+        );
+        assert!(find_free_ports(&[listener], &Protocol::TCP, loopback, range, 1).unwrap().is_empty());
+
+        assert!(find_free_ports(&[], &Protocol::ICMP, loopback, range, 1).is_err());
+        assert!(find_free_ports(&[], &Protocol::TCP, loopback, PortRange { start: 10, end: 5 }, 1).is_err());
+    }
+
+    #[test]
+    fn test_explain_port_usage() {
+        let conn = |local: &str, remote: &str, state: ConnectionState, pid: u32, name: &str| {
+            ConnectionInfo::new(Protocol::TCP, local.to_string(), 8080, remote.to_string(), 0, state, pid, name.to_string())
+        };
+        let connections = vec![
+            conn("127.0.0.1", "127.0.0.1", ConnectionState::TimeWait, 0, ""),
+            conn("0.0.0.0", "*", ConnectionState::Listening, 4321, "node"),
+            conn("192.168.1.5", "*", ConnectionState::Listening, 99, "other"),
+            ConnectionInfo::new(Protocol::TCP, "10.0.0.2".to_string(), 40000, "10.0.0.9".to_string(), 8080, ConnectionState::Established, 7, "curl".to_string()),
+        ];
+
+        let report = explain_port_usage(&connections, &Protocol::TCP, ip("127.0.0.1"), 8080, true);
+        assert_eq!(report.sockets.len(), 3, "the client connecting to 8080 is not on the port");
+        assert!(report.blocked);
+        assert_eq!(report.processes_to_stop.len(), 1);
+        assert_eq!(report.processes_to_stop[0].process_name, "node");
+        assert!(report.sockets[0].reason.contains("wildcard"));
+        assert!(report.sockets.iter().any(|s| s.state == "TIME_WAIT" && !s.blocks_bind));
+
+        let strict = explain_port_usage(&connections[..1], &Protocol::TCP, ip("127.0.0.1"), 8080, false);
+        assert!(strict.blocked, "TIME_WAIT blocks without SO_REUSEADDR");
+
+        let free = explain_port_usage(&connections[2..3], &Protocol::TCP, ip("127.0.0.1"), 8080, true);
+        assert!(!free.blocked);
+    }
+}
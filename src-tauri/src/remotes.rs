@@ -0,0 +1,230 @@
+use crate::models::{ConnectionInfo, PeerInfo, Protocol};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// How remote endpoints are grouped
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    /// One group per remote address
+    Host,
+    /// One group per /24 (IPv4) or /64 (IPv6)
+    Subnet,
+}
+
+/// What groups are ordered by; ties fall back to the group's address
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSort {
+    #[default]
+    Connections,
+    Processes,
+    Hosts,
+    Key,
+}
+
+/// A local process talking to a remote group
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupProcess {
+    pub pid: u32,
+    pub process_name: String,
+    pub connections: usize,
+}
+
+/// Remote endpoints sharing a host or subnet, and who talks to them
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteGroup {
+    /// The address, or the network in CIDR notation
+    pub key: String,
+    /// Reverse-DNS name, for host groups when it is known
+    pub hostname: Option<String>,
+    /// Scope, ASN and country of the first address seen in the group
+    pub peer: Option<PeerInfo>,
+    /// Distinct remote addresses in the group
+    pub hosts: usize,
+    pub connections: usize,
+    /// Connection count per state
+    pub states: BTreeMap<String, usize>,
+    /// Remote ports in use, ascending
+    pub remote_ports: Vec<u16>,
+    /// Most connections first
+    pub processes: Vec<GroupProcess>,
+}
+
+/// The remote end of a connection, if it has one
+fn remote_ip(conn: &ConnectionInfo) -> Option<IpAddr> {
+    if matches!(conn.protocol, Protocol::Unix(_)) || conn.remote_port == 0 || conn.state.is_listening() {
+        return None;
+    }
+    let ip = conn.remote_address.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().ok()?.to_canonical();
+    (!ip.is_unspecified() && !ip.is_loopback()).then_some(ip)
+}
+
+/// Whether `conn` was accepted by one of `listeners` rather than opened by this host
+fn is_inbound(conn: &ConnectionInfo, listeners: &[(&Protocol, u16)]) -> bool {
+    listeners.iter().any(|(protocol, port)| conn.local_port == *port && conn.protocol.same_kind(protocol))
+}
+
+/// Network address and prefix length of the subnet holding `ip`
+fn subnet_of(ip: IpAddr) -> (IpAddr, u8) {
+    match ip {
+        IpAddr::V4(v4) => (IpAddr::V4(Ipv4Addr::from(u32::from(v4) & 0xffff_ff00)), 24),
+        IpAddr::V6(v6) => (IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !((1u128 << 64) - 1))), 64),
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    hosts: BTreeSet<IpAddr>,
+    hostname: Option<String>,
+    peer: Option<PeerInfo>,
+    connections: usize,
+    states: BTreeMap<String, usize>,
+    remote_ports: BTreeSet<u16>,
+    processes: HashMap<(u32, String), usize>,
+}
+
+/// Group the remote ends of `connections` by host or subnet
+///
+/// Only outgoing connections count: listeners, Unix sockets and unconnected
+/// sockets have no remote end, and connections accepted on a local listener
+/// or going to loopback are not remotes this host talks to.
+pub fn group_remotes(connections: &[ConnectionInfo], by: GroupBy, sort: GroupSort, descending: bool) -> Vec<RemoteGroup> {
+    let listeners: Vec<(&Protocol, u16)> = connections
+        .iter()
+        .filter(|conn| conn.state.is_listening())
+        .map(|conn| (&conn.protocol, conn.local_port))
+        .collect();
+    // Keyed by the host, or the network address of the subnet
+    let mut groups: HashMap<IpAddr, Accumulator> = HashMap::new();
+    for conn in connections {
+        let Some(ip) = remote_ip(conn) else {
+            continue;
+        };
+        if is_inbound(conn, &listeners) {
+            continue;
+        }
+        let address = match by {
+            GroupBy::Host => ip,
+            GroupBy::Subnet => subnet_of(ip).0,
+        };
+        let group = groups.entry(address).or_default();
+        group.hosts.insert(ip);
+        if by == GroupBy::Host && group.hostname.is_none() {
+            group.hostname = conn.remote_hostname.clone();
+        }
+        if group.peer.is_none() {
+            group.peer = conn.remote_peer.clone();
+        }
+        group.connections += 1;
+        *group.states.entry(conn.state.to_string()).or_insert(0) += 1;
+        group.remote_ports.insert(conn.remote_port);
+        *group.processes.entry((conn.pid, conn.process_name.clone())).or_insert(0) += 1;
+    }
+
+    let mut result: Vec<(IpAddr, RemoteGroup)> = groups
+        .into_iter()
+        .map(|(address, group)| {
+            let mut processes: Vec<GroupProcess> = group
+                .processes
+                .into_iter()
+                .map(|((pid, process_name), connections)| GroupProcess { pid, process_name, connections })
+                .collect();
+            processes.sort_by(|a, b| b.connections.cmp(&a.connections).then(a.pid.cmp(&b.pid)));
+            let key = match by {
+                GroupBy::Host => address.to_string(),
+                GroupBy::Subnet => format!("{}/{}", address, subnet_of(address).1),
+            };
+            let group = RemoteGroup {
+                key,
+                hostname: group.hostname,
+                peer: group.peer,
+                hosts: group.hosts.len(),
+                connections: group.connections,
+                states: group.states,
+                remote_ports: group.remote_ports.into_iter().collect(),
+                processes,
+            };
+            (address, group)
+        })
+        .collect();
+
+    // Addresses compare numerically, so 9.0.0.0/24 comes before 10.0.0.0/24
+    result.sort_by(|(a_address, a), (b_address, b)| {
+        let order = match sort {
+            GroupSort::Connections => a.connections.cmp(&b.connections),
+            GroupSort::Processes => a.processes.len().cmp(&b.processes.len()),
+            GroupSort::Hosts => a.hosts.cmp(&b.hosts),
+            GroupSort::Key => a_address.cmp(b_address),
+        };
+        let order = if descending { order.reverse() } else { order };
+        order.then_with(|| a_address.cmp(b_address)).then_with(|| a.key.cmp(&b.key))
+    });
+    result.into_iter().map(|(_, group)| group).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    fn conn(remote: &str, remote_port: u16, state: ConnectionState, pid: u32, process: &str) -> ConnectionInfo {
+        ConnectionInfo::new(Protocol::TCP, "192.168.1.10".to_string(), 50000, remote.to_string(), remote_port, state, pid, process.to_string())
+    }
+
+    #[test]
+    fn test_group_by_host_and_subnet() {
+        let connections = vec![
+            conn("140.82.112.3", 443, ConnectionState::Established, 10, "firefox"),
+            conn("140.82.112.3", 443, ConnectionState::Established, 10, "firefox"),
+            conn("140.82.112.4", 22, ConnectionState::TimeWait, 20, "git"),
+            conn("::ffff:140.82.112.9", 443, ConnectionState::CloseWait, 10, "firefox"),
+            conn("2606:4700::6810:84e5", 443, ConnectionState::Established, 30, "curl"),
+            ConnectionInfo::new(Protocol::TCP, "0.0.0.0".to_string(), 80, "0.0.0.0".to_string(), 0, ConnectionState::Listening, 40, "nginx".to_string()),
+            conn("127.0.0.1", 5432, ConnectionState::Established, 50, "api"),
+        ];
+
+        let hosts = group_remotes(&connections, GroupBy::Host, GroupSort::Connections, true);
+        assert_eq!(hosts.len(), 4);
+        assert_eq!(hosts[0].key, "140.82.112.3");
+        assert_eq!(hosts[0].connections, 2);
+
+        let subnets = group_remotes(&connections, GroupBy::Subnet, GroupSort::Connections, true);
+        let keys: Vec<&str> = subnets.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(keys, vec!["140.82.112.0/24", "2606:4700::/64"]);
+        let github = &subnets[0];
+        assert_eq!((github.hosts, github.connections), (3, 4));
+        assert_eq!(github.remote_ports, vec![22, 443]);
+        assert_eq!(github.states.get("ESTABLISHED"), Some(&2));
+        assert_eq!(github.processes[0].process_name, "firefox");
+        assert_eq!(github.processes[0].connections, 3);
+
+        let ascending = group_remotes(&connections, GroupBy::Subnet, GroupSort::Processes, false);
+        assert_eq!(ascending[0].key, "2606:4700::/64");
+
+        let by_key = group_remotes(&connections, GroupBy::Subnet, GroupSort::Key, true);
+        assert_eq!(by_key[0].key, "2606:4700::/64");
+
+        let numeric = vec![
+            conn("10.0.0.1", 443, ConnectionState::Established, 10, "a"),
+            conn("9.0.0.1", 443, ConnectionState::Established, 10, "a"),
+        ];
+        let keys: Vec<String> = group_remotes(&numeric, GroupBy::Subnet, GroupSort::Key, false).into_iter().map(|g| g.key).collect();
+        assert_eq!(keys, vec!["9.0.0.0/24", "10.0.0.0/24"]);
+    }
+
+    #[test]
+    fn test_inbound_connections_are_not_remotes() {
+        let mut listener = conn("0.0.0.0", 0, ConnectionState::Listening, 40, "sshd");
+        listener.local_port = 22;
+        let mut accepted = conn("203.0.113.7", 61000, ConnectionState::Established, 41, "sshd");
+        accepted.local_port = 22;
+        let outgoing = conn("203.0.113.7", 443, ConnectionState::Established, 10, "firefox");
+
+        let groups = group_remotes(&[listener, accepted, outgoing], GroupBy::Host, GroupSort::Connections, true);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].connections, 1);
+        assert_eq!(groups[0].processes[0].process_name, "firefox");
+    }
+}
@@ -0,0 +1,332 @@
+use crate::filter::validate_protocol_filter;
+use crate::fs_util::{write_atomic, Backing};
+use crate::monitor::trends::LeakThresholds;
+use crate::platform::unified::BackendKind;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+
+/// File name of the settings inside the app config dir
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Current on-disk schema; older files are migrated step by step on load
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Connection table columns the user can hide
+pub const COLUMNS: [&str; 9] = [
+    "protocol",
+    "local_address",
+    "local_port",
+    "remote_address",
+    "remote_port",
+    "state",
+    "pid",
+    "process_name",
+    "unit",
+];
+
+/// Auto-refresh bounds, in seconds
+const MIN_REFRESH_INTERVAL: u32 = 1;
+const MAX_REFRESH_INTERVAL: u32 = 3600;
+/// History retention bounds, in days
+const MAX_HISTORY_RETENTION_DAYS: u32 = 365;
+
+/// User preferences that survive a restart
+///
+/// Missing keys take their default, so adding a field never needs a
+/// migration; renaming or reshaping one does (see `MIGRATIONS`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub auto_refresh: bool,
+    pub refresh_interval_secs: u32,
+    /// Protocol filter selected at startup (`all`, `tcp`, `unix`, ...)
+    pub default_protocol: String,
+    /// Forced backend; `None` keeps the automatic fallback chain
+    pub backend: Option<BackendKind>,
+    /// Keys from `COLUMNS`
+    pub hidden_columns: Vec<String>,
+    pub all_namespaces: bool,
+    pub reverse_dns: bool,
+    /// Local `.mmdb` or `.csv` ASN/GeoIP databases, consulted in order
+    pub geoip_databases: Vec<String>,
+    /// Seconds between background monitor snapshots (history, alerts)
+    pub monitor_interval_secs: u32,
+    pub history_enabled: bool,
+    pub history_retention_days: u32,
+    pub leak_detection: LeakThresholds,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            auto_refresh: false,
+            refresh_interval_secs: 5,
+            default_protocol: "all".to_string(),
+            backend: None,
+            hidden_columns: Vec::new(),
+            all_namespaces: false,
+            reverse_dns: false,
+            geoip_databases: Vec::new(),
+            monitor_interval_secs: 10,
+            history_enabled: true,
+            history_retention_days: crate::monitor::history::DEFAULT_RETENTION_DAYS,
+            leak_detection: LeakThresholds::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if !(MIN_REFRESH_INTERVAL..=MAX_REFRESH_INTERVAL).contains(&self.refresh_interval_secs) {
+            return Err(SettingsError::Invalid(format!(
+                "refresh interval must be {}-{} seconds, got {}",
+                MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL, self.refresh_interval_secs
+            )));
+        }
+        if !(MIN_REFRESH_INTERVAL..=MAX_REFRESH_INTERVAL).contains(&self.monitor_interval_secs) {
+            return Err(SettingsError::Invalid(format!(
+                "monitor interval must be {}-{} seconds, got {}",
+                MIN_REFRESH_INTERVAL, MAX_REFRESH_INTERVAL, self.monitor_interval_secs
+            )));
+        }
+        if !(1..=MAX_HISTORY_RETENTION_DAYS).contains(&self.history_retention_days) {
+            return Err(SettingsError::Invalid(format!(
+                "history retention must be 1-{} days, got {}",
+                MAX_HISTORY_RETENTION_DAYS, self.history_retention_days
+            )));
+        }
+        self.leak_detection.validate().map_err(SettingsError::Invalid)?;
+        validate_protocol_filter(&self.default_protocol).map_err(|e| SettingsError::Invalid(e.to_string()))?;
+        if let Some(column) = self.hidden_columns.iter().find(|column| !COLUMNS.contains(&column.as_str())) {
+            return Err(SettingsError::Invalid(format!("unknown column '{}'", column)));
+        }
+        if let Some(backend) = self.backend {
+            if !BackendKind::available().contains(&backend) {
+                return Err(SettingsError::Invalid(format!(
+                    "{} is not available on this platform",
+                    backend.display_name()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors from validating or persisting settings
+#[derive(Debug, Clone)]
+pub enum SettingsError {
+    /// A value failed validation
+    Invalid(String),
+    /// Reading, parsing or writing the settings file failed
+    Io(String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Invalid(msg) => write!(f, "Invalid settings: {}", msg),
+            SettingsError::Io(msg) => write!(f, "Settings I/O error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// `MIGRATIONS[n]` upgrades a version-`n` document to version `n + 1`
+const MIGRATIONS: [fn(&mut Map<String, Value>); SETTINGS_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Version 0 is any file written before the `version` key existed;
+/// its keys already match version 1
+fn migrate_v0_to_v1(_settings: &mut Map<String, Value>) {}
+
+/// Bring a settings document of any known version up to `SETTINGS_VERSION`
+pub fn migrate(document: Value) -> Result<Settings, SettingsError> {
+    let Value::Object(mut map) = document else {
+        return Err(SettingsError::Io("settings file is not a JSON object".to_string()));
+    };
+    let version = map.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        return Err(SettingsError::Io(format!(
+            "settings were written by a newer version (schema {})",
+            version
+        )));
+    }
+
+    for step in &MIGRATIONS[version as usize..] {
+        step(&mut map);
+    }
+    map.insert("version".to_string(), SETTINGS_VERSION.into());
+    serde_json::from_value(Value::Object(map)).map_err(|e| SettingsError::Io(e.to_string()))
+}
+
+/// Settings shared by every Tauri command, written back on each change
+pub struct SettingsStore {
+    backing: Mutex<Backing>,
+    settings: RwLock<Settings>,
+}
+
+impl SettingsStore {
+    pub fn new() -> Self {
+        Self {
+            backing: Mutex::new(Backing::Memory),
+            settings: RwLock::new(Settings::default()),
+        }
+    }
+
+    /// Read settings from `path` and persist to it from now on
+    ///
+    /// A missing file yields the defaults. A file that cannot be read, parsed
+    /// or validated, or comes from a newer version, is copied to `<path>.bak`
+    /// and the defaults are used without ever saving over it, so a bad edit
+    /// never keeps the app from starting and a downgrade never loses settings.
+    pub fn load(&self, path: PathBuf) -> Result<Settings, SettingsError> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                *self.backing.lock().unwrap() = Backing::File(path);
+                return Ok(self.get());
+            }
+            Err(e) => return Err(self.refuse_saving(format!("{}: {}", path.display(), e))),
+        };
+
+        let loaded = serde_json::from_str(&content)
+            .map_err(|e| SettingsError::Io(e.to_string()))
+            .and_then(migrate)
+            .and_then(|settings| settings.validate().map(|_| settings));
+        match loaded {
+            Ok(settings) => {
+                *self.settings.write().unwrap() = settings.clone();
+                *self.backing.lock().unwrap() = Backing::File(path);
+                Ok(settings)
+            }
+            Err(e) => {
+                let _ = fs::copy(&path, path.with_extension("json.bak"));
+                Err(self.refuse_saving(format!("{}: {}", path.display(), e)))
+            }
+        }
+    }
+
+    fn refuse_saving(&self, message: String) -> SettingsError {
+        *self.backing.lock().unwrap() = Backing::ReadOnly(message.clone());
+        SettingsError::Io(message)
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Validate and store a complete settings object
+    pub fn set(&self, settings: Settings) -> Result<Settings, SettingsError> {
+        let mut current = self.settings.write().unwrap();
+        self.commit(&mut current, settings)
+    }
+
+    /// Change a single field, e.g. from a command that toggles one option
+    ///
+    /// The write lock is held from read to save, so concurrent updates of
+    /// different fields never overwrite each other.
+    pub fn update(&self, change: impl FnOnce(&mut Settings)) -> Result<Settings, SettingsError> {
+        let mut current = self.settings.write().unwrap();
+        let mut settings = current.clone();
+        change(&mut settings);
+        self.commit(&mut current, settings)
+    }
+
+    fn commit(&self, current: &mut Settings, mut settings: Settings) -> Result<Settings, SettingsError> {
+        settings.version = SETTINGS_VERSION;
+        settings.validate()?;
+        self.save(&settings)?;
+        *current = settings.clone();
+        Ok(settings)
+    }
+
+    fn save(&self, settings: &Settings) -> Result<(), SettingsError> {
+        let path = match &*self.backing.lock().unwrap() {
+            Backing::Memory => return Ok(()),
+            Backing::File(path) => path.clone(),
+            Backing::ReadOnly(reason) => return Err(SettingsError::Io(format!("not saving over the file: {}", reason))),
+        };
+        let json = serde_json::to_string_pretty(settings).map_err(|e| SettingsError::Io(e.to_string()))?;
+        write_atomic(&path, &json).map_err(|e| SettingsError::Io(format!("{}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_fills_defaults_and_rejects_newer() {
+        let settings = migrate(serde_json::json!({ "refresh_interval_secs": 10, "hidden_columns": ["pid"] })).unwrap();
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.refresh_interval_secs, 10);
+        assert_eq!(settings.hidden_columns, vec!["pid".to_string()]);
+        assert_eq!(settings.default_protocol, "all");
+
+        assert!(migrate(serde_json::json!({ "version": SETTINGS_VERSION + 1 })).is_err());
+        assert!(migrate(serde_json::json!([1, 2])).is_err());
+    }
+
+    #[test]
+    fn test_newer_file_is_never_overwritten() {
+        let path = std::env::temp_dir().join(format!("portviewer-settings-newer-{}.json", std::process::id()));
+        let newer = format!(r#"{{ "version": {}, "refresh_interval_secs": 7 }}"#, SETTINGS_VERSION + 1);
+        fs::write(&path, &newer).unwrap();
+
+        let store = SettingsStore::new();
+        assert!(store.load(path.clone()).is_err());
+        assert!(store.update(|settings| settings.reverse_dns = true).is_err());
+        assert_eq!(store.get().refresh_interval_secs, Settings::default().refresh_interval_secs);
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+
+        fs::remove_file(&path).unwrap();
+        let _ = fs::remove_file(path.with_extension("json.bak"));
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let store = std::sync::Arc::new(SettingsStore::new());
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    store
+                        .update(|settings| {
+                            if i % 2 == 0 {
+                                settings.refresh_interval_secs += 1;
+                            } else {
+                                settings.monitor_interval_secs += 1;
+                            }
+                        })
+                        .unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let settings = store.get();
+        assert_eq!(settings.refresh_interval_secs, Settings::default().refresh_interval_secs + 4);
+        assert_eq!(settings.monitor_interval_secs, Settings::default().monitor_interval_secs + 4);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Settings::default().validate().is_ok());
+
+        let invalid = [
+            Settings { refresh_interval_secs: 0, ..Settings::default() },
+            Settings { default_protocol: "carrier-pigeon".to_string(), ..Settings::default() },
+            Settings { hidden_columns: vec!["colour".to_string()], ..Settings::default() },
+        ];
+        for settings in invalid {
+            assert!(settings.validate().is_err(), "{:?}", settings);
+        }
+    }
+}
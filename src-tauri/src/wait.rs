@@ -0,0 +1,295 @@
+use crate::models::{ConnectionInfo, ConnectionState, Protocol};
+use crate::platform::NetworkError;
+use crate::ports::{addresses_overlap, parse_bind_address};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Collection is not free; polling faster than this only burns CPU
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+pub const MAX_WAIT_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// What to wait for
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitCondition {
+    /// Something matching the criteria listens (or, for UDP, is bound)
+    Listening,
+    /// Nothing matching the criteria holds the port any more
+    Released,
+}
+
+/// Which sockets count; only `port` is required
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WaitCriteria {
+    pub port: u16,
+    /// `tcp` (default), `udp` or `sctp`
+    pub protocol: Option<String>,
+    /// Bind address; a wildcard listener satisfies a specific address
+    pub address: Option<String>,
+    /// Case-insensitive substring of the process name
+    pub process: Option<String>,
+}
+
+/// Criteria checked once up front so polling never fails on bad input
+struct Matcher {
+    port: u16,
+    protocol: Protocol,
+    address: Option<IpAddr>,
+    process: Option<String>,
+}
+
+impl Matcher {
+    fn new(criteria: &WaitCriteria) -> Result<Self, WaitError> {
+        if criteria.port == 0 {
+            return Err(WaitError::Invalid("port must be 1-65535".to_string()));
+        }
+        let protocol = criteria
+            .protocol
+            .as_deref()
+            .unwrap_or("tcp")
+            .parse::<Protocol>()
+            .map_err(|e| WaitError::Invalid(e.to_string()))?;
+        let address = match criteria.address.as_deref() {
+            Some(address) => Some(
+                parse_bind_address(address)
+                    .ok_or_else(|| WaitError::Invalid(format!("'{}' is not an IP address", address)))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            port: criteria.port,
+            protocol,
+            address,
+            process: criteria.process.as_ref().map(|process| process.to_lowercase()),
+        })
+    }
+
+    fn matches(&self, conn: &ConnectionInfo) -> bool {
+        conn.local_port == self.port
+            && conn.protocol.same_kind(&self.protocol)
+            && !conn.context.foreign_namespace
+            && self.address.is_none_or(|address| {
+                parse_bind_address(&conn.local_address).is_some_and(|local| addresses_overlap(local, address))
+            })
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|process| conn.process_name.to_lowercase().contains(process))
+    }
+
+    /// Sockets that currently satisfy (listening) or violate (released) the condition
+    fn relevant<'a>(&self, condition: WaitCondition, connections: &'a [ConnectionInfo]) -> Vec<&'a ConnectionInfo> {
+        connections
+            .iter()
+            .filter(|conn| self.matches(conn))
+            .filter(|conn| match condition {
+                WaitCondition::Listening => conn.state.is_listening(),
+                // TIME_WAIT leftovers do not keep a SO_REUSEADDR listener out
+                WaitCondition::Released => conn.state != ConnectionState::TimeWait,
+            })
+            .collect()
+    }
+}
+
+/// A socket seen on the port when the wait ended
+#[derive(Debug, Clone, Serialize)]
+pub struct WaitSocket {
+    pub local_address: String,
+    pub state: String,
+    pub pid: u32,
+    pub process_name: String,
+}
+
+/// How a successful wait ended
+#[derive(Debug, Clone, Serialize)]
+pub struct WaitOutcome {
+    pub condition: WaitCondition,
+    pub port: u16,
+    pub elapsed_ms: u64,
+    pub polls: u32,
+    /// The listeners found; empty for a release
+    pub sockets: Vec<WaitSocket>,
+}
+
+/// Errors from a wait
+#[derive(Debug, Clone)]
+pub enum WaitError {
+    /// Bad criteria or timing options
+    Invalid(String),
+    /// The condition did not hold before the timeout
+    TimedOut { waited: Duration, last_error: Option<String> },
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitError::Invalid(msg) => write!(f, "Invalid wait: {}", msg),
+            WaitError::TimedOut { waited, last_error } => {
+                write!(f, "Timed out after {:.1}s", waited.as_secs_f64())?;
+                if let Some(error) = last_error {
+                    write!(f, " (last collection error: {})", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+/// Poll snapshots until `condition` holds for `criteria`, or `timeout` passes
+///
+/// `snapshot` is usually `ConnectionCollector::snapshot`, which neither
+/// cancels nor is cancelled by the table refresh. A failing collection is
+/// retried on the next tick rather than ending the wait, since a backend
+/// hiccup says nothing about the port.
+pub async fn wait_for<F, Fut>(
+    mut snapshot: F,
+    condition: WaitCondition,
+    criteria: &WaitCriteria,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Result<WaitOutcome, WaitError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<ConnectionInfo>, NetworkError>>,
+{
+    let matcher = Matcher::new(criteria)?;
+    if timeout.is_zero() || timeout > MAX_WAIT_TIMEOUT {
+        return Err(WaitError::Invalid(format!("timeout must be up to {}s", MAX_WAIT_TIMEOUT.as_secs())));
+    }
+    if poll_interval < MIN_POLL_INTERVAL {
+        return Err(WaitError::Invalid(format!(
+            "poll interval must be at least {}ms",
+            MIN_POLL_INTERVAL.as_millis()
+        )));
+    }
+
+    let started = Instant::now();
+    let deadline = started + timeout;
+    let mut polls = 0;
+    let mut last_error = None;
+
+    loop {
+        polls += 1;
+        // A collection that overruns the deadline counts as a timeout too
+        match tokio::time::timeout_at(deadline.into(), snapshot()).await {
+            Ok(Ok(connections)) => {
+                let relevant = matcher.relevant(condition, &connections);
+                let satisfied = match condition {
+                    WaitCondition::Listening => !relevant.is_empty(),
+                    WaitCondition::Released => relevant.is_empty(),
+                };
+                if satisfied {
+                    return Ok(WaitOutcome {
+                        condition,
+                        port: matcher.port,
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                        polls,
+                        sockets: relevant
+                            .into_iter()
+                            .map(|conn| WaitSocket {
+                                local_address: conn.local_address.clone(),
+                                state: conn.state.to_string(),
+                                pid: conn.pid,
+                                process_name: conn.process_name.clone(),
+                            })
+                            .collect(),
+                    });
+                }
+            }
+            Ok(Err(e)) => last_error = Some(e.to_string()),
+            Err(_) => {}
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(WaitError::TimedOut {
+                waited: started.elapsed(),
+                last_error,
+            });
+        }
+        tokio::time::sleep(poll_interval.min(deadline - now)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn listener(port: u16, process: &str) -> ConnectionInfo {
+        ConnectionInfo::new(
+            Protocol::TCP,
+            "0.0.0.0".to_string(),
+            port,
+            "*".to_string(),
+            0,
+            ConnectionState::Listening,
+            10,
+            process.to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_listener_then_release() {
+        // The listener shows up on the third poll and is gone from the fifth
+        let calls = AtomicU32::new(0);
+        let snapshot = || {
+            let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                Ok(match call {
+                    3 | 4 => vec![listener(5432, "postgres")],
+                    _ => vec![listener(8080, "nginx")],
+                })
+            }
+        };
+        let criteria = WaitCriteria {
+            port: 5432,
+            address: Some("127.0.0.1".to_string()),
+            process: Some("POSTGRES".to_string()),
+            ..WaitCriteria::default()
+        };
+        let interval = MIN_POLL_INTERVAL;
+
+        let up = wait_for(snapshot, WaitCondition::Listening, &criteria, Duration::from_secs(5), interval)
+            .await
+            .unwrap();
+        assert_eq!(up.polls, 3);
+        assert_eq!(up.sockets[0].process_name, "postgres");
+
+        let down = wait_for(snapshot, WaitCondition::Released, &criteria, Duration::from_secs(5), interval)
+            .await
+            .unwrap();
+        assert_eq!(down.polls, 2);
+        assert!(down.sockets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out_and_validates() {
+        let snapshot = || async { Ok(Vec::new()) };
+        let criteria = WaitCriteria {
+            port: 5432,
+            ..WaitCriteria::default()
+        };
+        let result = wait_for(
+            snapshot,
+            WaitCondition::Listening,
+            &criteria,
+            Duration::from_millis(250),
+            MIN_POLL_INTERVAL,
+        )
+        .await;
+        assert!(matches!(result, Err(WaitError::TimedOut { .. })));
+
+        let no_port = WaitCriteria::default();
+        let result = wait_for(snapshot, WaitCondition::Released, &no_port, DEFAULT_WAIT_TIMEOUT, DEFAULT_POLL_INTERVAL).await;
+        assert!(matches!(result, Err(WaitError::Invalid(_))));
+    }
+}
@@ -0,0 +1,15 @@
+const UNITS = ['B', 'KB', 'MB', 'GB', 'TB']
+
+/** Human-readable byte count, e.g. `1.5 MB` */
+export const formatBytes = (bytes: number): string => {
+  let value = bytes
+  let unit = 0
+  while (value >= 1024 && unit < UNITS.length - 1) {
+    value /= 1024
+    unit++
+  }
+  return `${unit === 0 ? value.toFixed(0) : value.toFixed(1)} ${UNITS[unit]}`
+}
+
+/** Human-readable rate from bytes per second, e.g. `12.0 KB/s` */
+export const formatRate = (bytesPerSecond: number): string => `${formatBytes(bytesPerSecond)}/s`